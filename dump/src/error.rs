@@ -0,0 +1,38 @@
+use std::fmt::{self, Display};
+use std::io;
+use std::path::PathBuf;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub enum Error {
+    Usage(String),
+    Read(PathBuf, io::Error),
+    NotElf(PathBuf),
+    Manifest(usize),
+    NoSymbols,
+    Write(io::Error),
+}
+
+impl Display for Error {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Usage(message) => {
+                write!(formatter, "{message}\n\nUsage: inventory-dump [--json] <FILE>")
+            }
+            Error::Read(path, error) => {
+                write!(formatter, "failed to read {}: {}", path.display(), error)
+            }
+            Error::NotElf(path) => {
+                write!(formatter, "{} is not an ELF executable or shared library", path.display())
+            }
+            Error::Manifest(offset) => write!(
+                formatter,
+                "malformed inventory_manifest section at offset {offset}",
+            ),
+            Error::NoSymbols => formatter.write_str(
+                "found neither an inventory_manifest section nor a symbol table; build with the \"manifest\" feature of inventory to describe submissions in stripped binaries",
+            ),
+            Error::Write(error) => write!(formatter, "failed to write output: {error}"),
+        }
+    }
+}
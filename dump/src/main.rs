@@ -0,0 +1,110 @@
+//! List the plugins registered by `inventory` in an ELF executable or shared
+//! library, without running it.
+//!
+//! ```console
+//! $ inventory-dump target/release/server
+//! $ inventory-dump --json target/release/libplugins.so
+//! ```
+//!
+//! Submissions are described in full if the binary was built with the
+//! `"manifest"` feature of inventory, which records the registry, location and
+//! label of every `submit!` in a link section named `inventory_manifest`.
+//! Otherwise the symbol table is searched for the statics that `submit!`
+//! generates, which tells the module of each submission but not its registry.
+
+mod error;
+mod manifest;
+mod output;
+mod symbols;
+
+use crate::error::{Error, Result};
+use object::{BinaryFormat, Object, ObjectSection};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process;
+
+const USAGE: &str = "\
+Usage: inventory-dump [--json] <FILE>
+
+List the plugins registered by inventory in an ELF executable or shared library.
+
+Options:
+      --json  Print JSON instead of a table
+  -h, --help  Print help
+";
+
+fn main() {
+    if let Err(error) = try_main() {
+        let _ = writeln!(io::stderr(), "error: {error}");
+        process::exit(1);
+    }
+}
+
+fn try_main() -> Result<()> {
+    let Some(args) = parse_args(env::args_os().skip(1))? else {
+        print!("{USAGE}");
+        return Ok(());
+    };
+
+    let data = fs::read(&args.path).map_err(|error| Error::Read(args.path.clone(), error))?;
+    let file = object::File::parse(&*data).map_err(|_| Error::NotElf(args.path.clone()))?;
+    if file.format() != BinaryFormat::Elf {
+        return Err(Error::NotElf(args.path));
+    }
+
+    let (source, submissions) = match file.section_by_name(manifest::SECTION) {
+        Some(section) => {
+            let data = section.data().map_err(|_| Error::Manifest(0))?;
+            (output::Source::Manifest, manifest::parse(data)?)
+        }
+        None => (output::Source::Symbols, symbols::find(&file)?),
+    };
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    if args.json {
+        output::json(&mut stdout, source, submissions)
+    } else {
+        if source == output::Source::Symbols {
+            let _ = writeln!(
+                io::stderr(),
+                "note: {} has no {} section; registries are unknown unless it is built with the \"manifest\" feature of inventory",
+                args.path.display(),
+                manifest::SECTION,
+            );
+        }
+        output::table(&mut stdout, submissions)
+    }
+    .map_err(Error::Write)
+}
+
+struct Args {
+    json: bool,
+    path: PathBuf,
+}
+
+// None if help was requested.
+fn parse_args(args: impl Iterator<Item = OsString>) -> Result<Option<Args>> {
+    let mut json = false;
+    let mut path = None;
+    for arg in args {
+        match arg.to_str() {
+            Some("--json") => json = true,
+            Some("-h" | "--help") => return Ok(None),
+            Some(flag) if flag.starts_with('-') => {
+                return Err(Error::Usage(format!("unrecognized option `{flag}`")));
+            }
+            _ if path.is_some() => {
+                return Err(Error::Usage("expected exactly one file".to_owned()));
+            }
+            _ => path = Some(PathBuf::from(arg)),
+        }
+    }
+    match path {
+        Some(path) => Ok(Some(Args { json, path })),
+        None => Err(Error::Usage("expected exactly one file".to_owned())),
+    }
+}
@@ -0,0 +1,104 @@
+// Reader for the records that inventory's "manifest" feature places in a link
+// section. See the "Manifest" section of the inventory crate documentation for
+// the format.
+
+use crate::error::{Error, Result};
+use serde::Serialize;
+
+pub const SECTION: &str = "inventory_manifest";
+
+const VERSION: u8 = 1;
+
+#[derive(Serialize)]
+pub struct Submission {
+    // Plugin type, or path of a named registry, if known. The JSON output
+    // groups submissions by it.
+    #[serde(skip)]
+    pub registry: Option<String>,
+    #[serde(rename = "crate")]
+    pub krate: String,
+    pub module_path: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub label: Option<String>,
+}
+
+pub fn parse(section: &[u8]) -> Result<Vec<Submission>> {
+    let mut submissions = Vec::new();
+    let mut offset = 0;
+    while offset < section.len() {
+        // Padding between records.
+        if section[offset] == 0 {
+            offset += 1;
+            continue;
+        }
+        let mut record = Reader {
+            section,
+            start: offset,
+            pos: offset + 1,
+            end: section.len(),
+        };
+        let len = record.u32()? as usize;
+        if len < 5 || len > section.len() - offset {
+            return Err(Error::Manifest(offset));
+        }
+        record.end = offset + len;
+        if section[offset] == VERSION {
+            submissions.push(record.submission()?);
+        }
+        offset += len;
+    }
+    Ok(submissions)
+}
+
+struct Reader<'a> {
+    section: &'a [u8],
+    start: usize,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn submission(&mut self) -> Result<Submission> {
+        let line = self.u32()?;
+        let registry = self.string()?;
+        let krate = self.string()?;
+        let module_path = self.string()?;
+        let file = self.string()?;
+        let label = self.string()?;
+        // Anything after the fields above belongs to a later revision of the
+        // format, and is ignored.
+        Ok(Submission {
+            registry: Some(registry),
+            krate,
+            module_path,
+            file: Some(file),
+            line: Some(line),
+            label: if label.is_empty() { None } else { Some(label) },
+        })
+    }
+
+    fn bytes(&mut self, n: usize) -> Result<&'a [u8]> {
+        if n > self.end - self.pos {
+            return Err(Error::Manifest(self.start));
+        }
+        let bytes = &self.section[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.bytes(2)?;
+        let len = u16::from_le_bytes([len[0], len[1]]);
+        let bytes = self.bytes(usize::from(len))?;
+        match String::from_utf8(bytes.to_vec()) {
+            Ok(string) => Ok(string),
+            Err(_) => Err(Error::Manifest(self.start)),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::manifest::Submission;
+use serde::Serialize;
+use std::io::{self, Write};
+
+#[derive(Serialize, Copy, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    Manifest,
+    Symbols,
+}
+
+#[derive(Serialize)]
+struct Dump<'a> {
+    source: Source,
+    registries: Vec<Registry<'a>>,
+}
+
+#[derive(Serialize)]
+struct Registry<'a> {
+    name: Option<&'a str>,
+    submissions: Vec<&'a Submission>,
+}
+
+pub fn json(
+    out: &mut dyn Write,
+    source: Source,
+    mut submissions: Vec<Submission>,
+) -> io::Result<()> {
+    sort(&mut submissions);
+    let mut registries: Vec<Registry> = Vec::new();
+    for submission in &submissions {
+        let name = submission.registry.as_deref();
+        match registries.last_mut() {
+            Some(registry) if registry.name == name => registry.submissions.push(submission),
+            _ => registries.push(Registry {
+                name,
+                submissions: vec![submission],
+            }),
+        }
+    }
+    let dump = Dump { source, registries };
+    serde_json::to_writer_pretty(&mut *out, &dump)?;
+    writeln!(out)
+}
+
+pub fn table(out: &mut dyn Write, mut submissions: Vec<Submission>) -> io::Result<()> {
+    sort(&mut submissions);
+    let header = ["REGISTRY", "CRATE", "MODULE", "LOCATION", "LABEL"];
+    let rows: Vec<[String; 5]> = submissions
+        .iter()
+        .map(|submission| {
+            [
+                submission
+                    .registry
+                    .clone()
+                    .unwrap_or_else(|| "?".to_owned()),
+                submission.krate.clone(),
+                submission.module_path.clone(),
+                match (&submission.file, submission.line) {
+                    (Some(file), Some(line)) => format!("{file}:{line}"),
+                    _ => "?".to_owned(),
+                },
+                submission.label.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let header = header.map(str::to_owned);
+    for row in Some(&header).into_iter().chain(&rows) {
+        let mut line = String::new();
+        for (i, cell) in row.iter().enumerate() {
+            if i > 0 {
+                line.push_str("  ");
+            }
+            line.push_str(cell);
+            if i + 1 < row.len() {
+                let padding = widths[i] - cell.chars().count();
+                line.extend(std::iter::repeat(' ').take(padding));
+            }
+        }
+        writeln!(out, "{}", line.trim_end())?;
+    }
+    Ok(())
+}
+
+// Submissions are listed by registry, then by location.
+fn sort(submissions: &mut [Submission]) {
+    submissions.sort_by(|a, b| {
+        let a = (&a.registry, &a.module_path, &a.file, a.line);
+        let b = (&b.registry, &b.module_path, &b.file, b.line);
+        a.cmp(&b)
+    });
+}
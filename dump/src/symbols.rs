@@ -0,0 +1,35 @@
+// Fallback for binaries built without inventory's "manifest" feature. Every
+// submit! defines a static named __INVENTORY inside of an anonymous `const _`
+// block, so each such symbol in the symbol table is one submission from the
+// module that contains the block.
+
+use crate::error::{Error, Result};
+use crate::manifest::Submission;
+use object::{Object, ObjectSymbol};
+
+pub fn find(file: &object::File) -> Result<Vec<Submission>> {
+    if file.symbol_table().is_none() {
+        return Err(Error::NoSymbols);
+    }
+    let mut submissions = Vec::new();
+    for symbol in file.symbols() {
+        let Ok(name) = symbol.name() else {
+            continue;
+        };
+        // The alternate format leaves out the hash.
+        let demangled = format!("{:#}", rustc_demangle::demangle(name));
+        let Some(module_path) = demangled.strip_suffix("::_::__INVENTORY") else {
+            continue;
+        };
+        let krate = module_path.split("::").next().unwrap_or(module_path);
+        submissions.push(Submission {
+            registry: None,
+            krate: krate.to_owned(),
+            module_path: module_path.to_owned(),
+            file: None,
+            line: None,
+            label: None,
+        });
+    }
+    Ok(submissions)
+}
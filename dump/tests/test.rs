@@ -0,0 +1,81 @@
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::process::Command;
+
+// Each plugin holds the line of its submission, which the manifest records.
+pub struct Audited(pub &'static str, pub u32);
+
+inventory::collect!(Audited);
+
+inventory::submit!(#![label("first")] Audited("a", line!()));
+
+inventory::submit!(Audited("b", line!()));
+
+fn line(name: &str) -> u32 {
+    inventory::iter::<Audited>
+        .into_iter()
+        .find(|audited| audited.0 == name)
+        .unwrap()
+        .1
+}
+
+fn dump(args: &[&str]) -> String {
+    let exe = env::current_exe().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_inventory-dump"))
+        .args(args)
+        .arg(exe)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_table() {
+    let table = dump(&[]);
+    let mut lines = table.lines();
+    let header: Vec<&str> = lines.next().unwrap().split_whitespace().collect();
+    assert_eq!(header, ["REGISTRY", "CRATE", "MODULE", "LOCATION", "LABEL"]);
+    let rows: Vec<Vec<&str>> = lines
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>())
+        .filter(|row| row[0] == "Audited")
+        .collect();
+    let first = format!("{}:{}", file!(), line("a"));
+    let second = format!("{}:{}", file!(), line("b"));
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0], ["Audited", "test", "test", &first, "first"]);
+    assert_eq!(rows[1], ["Audited", "test", "test", &second]);
+}
+
+#[test]
+fn test_json() {
+    let json = dump(&["--json"]);
+    let dump: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(dump["source"], "manifest");
+    let registry = dump["registries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|registry| registry["name"] == "Audited")
+        .unwrap();
+    assert_eq!(
+        registry["submissions"],
+        serde_json::json!([
+            {
+                "crate": "test",
+                "module_path": "test",
+                "file": file!(),
+                "line": line("a"),
+                "label": "first",
+            },
+            {
+                "crate": "test",
+                "module_path": "test",
+                "file": file!(),
+                "line": line("b"),
+                "label": null,
+            },
+        ]),
+    );
+}
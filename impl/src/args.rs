@@ -0,0 +1,49 @@
+use syn::parse::{Parse, ParseStream, Result};
+use syn::{Expr, Ident, Path, Token, Type};
+
+pub(crate) struct Args {
+    pub ty: Type,
+    pub fields: Vec<Field>,
+    // Path of the inventory crate, given as `crate = path` by code that
+    // refers to it by some other name.
+    pub krate: Option<Path>,
+}
+
+// Either `member = value`, or just `member` to have its value filled in from
+// the annotated item.
+pub(crate) struct Field {
+    pub member: Ident,
+    pub value: Option<Expr>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ty: Type = input.parse()?;
+        let mut fields = Vec::new();
+        let mut krate = None;
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            if input.peek(Token![crate]) {
+                let token = input.parse::<Token![crate]>()?;
+                input.parse::<Token![=]>()?;
+                if krate.is_some() {
+                    return Err(syn::Error::new(token.span, "duplicate crate"));
+                }
+                krate = Some(Path::parse_mod_style(input)?);
+                continue;
+            }
+            let member: Ident = input.parse()?;
+            let value = if input.peek(Token![=]) {
+                input.parse::<Token![=]>()?;
+                Some(input.parse()?)
+            } else {
+                None
+            };
+            fields.push(Field { member, value });
+        }
+        Ok(Args { ty, fields, krate })
+    }
+}
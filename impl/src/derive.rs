@@ -0,0 +1,256 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    Data, DeriveInput, Error, Fields, Ident, LitStr, Member, Path, Result, Token, Type,
+    TypeReference, WhereClause,
+};
+
+#[derive(Default)]
+struct Attrs {
+    key: Option<Key>,
+    unique: bool,
+    on_duplicate: Option<Ident>,
+    order: Option<Member>,
+    export: Option<LitStr>,
+    ffi: Option<LitStr>,
+    krate: Option<Path>,
+}
+
+struct Key {
+    member: Member,
+    ty: Option<Type>,
+}
+
+pub(crate) fn derive(input: &DeriveInput) -> Result<TokenStream> {
+    let attrs = parse_attrs(input)?;
+    let krate = attrs
+        .krate
+        .as_ref()
+        .map_or_else(|| quote!(::inventory), ToTokens::to_token_stream);
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut where_clause = where_clause.cloned();
+    if !input.generics.params.is_empty() {
+        where_clause
+            .get_or_insert_with(|| syn::parse_quote!(where))
+            .predicates
+            .push(syn::parse_quote!(#ident #ty_generics: 'static));
+    }
+
+    // A static inside of a generic impl is shared by every instantiation, so
+    // generic types find their registry by TypeId instead.
+    let registry = if let Some(symbol) = &attrs.export {
+        quote! {
+            #krate::__exported_registry!(#symbol)
+        }
+    } else if input.generics.params.is_empty() {
+        quote! {
+            static REGISTRY: #krate::Registry = #krate::Registry::new();
+            &REGISTRY
+        }
+    } else {
+        quote! {
+            static REGISTRIES: #krate::__private::GenericRegistry =
+                #krate::__private::GenericRegistry::new();
+            REGISTRIES.get::<Self>()
+        }
+    };
+
+    let unique = if attrs.unique {
+        let policy = attrs
+            .on_duplicate
+            .unwrap_or_else(|| Ident::new("Panic", Span::call_site()));
+        Some(quote! {
+            const UNIQUE: #krate::__private::Option<#krate::__private::Unique<Self>> =
+                #krate::__private::Option::Some(#krate::__private::Unique::new(
+                    #krate::OnDuplicate::#policy,
+                ));
+        })
+    } else {
+        None
+    };
+
+    let order = attrs.order.as_ref().map(|member| {
+        quote! {
+            const ORDER: #krate::__private::Option<
+                fn(&Self, &Self) -> #krate::__private::Ordering,
+            > = #krate::__private::Option::Some(|a, b| {
+                #krate::__private::Ord::cmp(&a.#member, &b.#member)
+            });
+        }
+    });
+
+    let keyed = match &attrs.key {
+        Some(key) => Some(keyed(input, key, &krate, where_clause.as_ref())?),
+        None => None,
+    };
+
+    let ffi = attrs.ffi.as_ref().map(|prefix| {
+        quote! {
+            #krate::__ffi!(#ident, #prefix);
+        }
+    });
+
+    // The instantiations of a generic type are not known, so only registries
+    // of non-generic types are listed by inventory::registries.
+    let info = if input.generics.params.is_empty() {
+        Some(quote! {
+            #krate::__registry_info!(#ident);
+        })
+    } else {
+        None
+    };
+
+    Ok(quote! {
+        impl #impl_generics #krate::Collect for #ident #ty_generics #where_clause {
+            const NAME: &'static str = #krate::__private::stringify!(#ident #ty_generics);
+
+            #[inline]
+            fn registry() -> &'static #krate::Registry {
+                #registry
+            }
+
+            #[inline]
+            unsafe fn from_erased(value: *const (), index: usize) -> &'static Self {
+                unsafe { #krate::__private::from_erased::<Self>(value, index) }
+            }
+
+            #[inline]
+            fn erase(value: &&'static Self) -> *const () {
+                #krate::__private::erase::<Self>(value)
+            }
+
+            #unique
+            #order
+        }
+
+        #keyed
+        #ffi
+        #info
+    })
+}
+
+fn keyed(
+    input: &DeriveInput,
+    key: &Key,
+    krate: &TokenStream,
+    where_clause: Option<&WhereClause>,
+) -> Result<TokenStream> {
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, _) = input.generics.split_for_impl();
+    let member = &key.member;
+    let (key_ty, key_expr) = match &key.ty {
+        Some(ty) => (quote!(#ty), quote!(&self.#member)),
+        None => match field_type(input, member) {
+            Some(Type::Reference(TypeReference { elem, .. })) => {
+                (quote!(#elem), quote!(self.#member))
+            }
+            Some(ty) => (quote!(#ty), quote!(&self.#member)),
+            None => return Err(Error::new_spanned(member, "no such field")),
+        },
+    };
+    Ok(quote! {
+        impl #impl_generics #krate::Keyed for #ident #ty_generics #where_clause {
+            type Key = #key_ty;
+
+            fn key(&self) -> &Self::Key {
+                #key_expr
+            }
+        }
+    })
+}
+
+fn parse_attrs(input: &DeriveInput) -> Result<Attrs> {
+    let mut attrs = Attrs::default();
+    for attr in &input.attrs {
+        if !attr.path().is_ident("inventory") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("key") || meta.path.is_ident("unique_by") {
+                if attrs.key.is_some() {
+                    return Err(meta.error("duplicate key"));
+                }
+                let member: Member = meta.value()?.parse()?;
+                let ty = if meta.input.peek(Token![:]) {
+                    meta.input.parse::<Token![:]>()?;
+                    Some(meta.input.parse()?)
+                } else {
+                    None
+                };
+                attrs.unique = meta.path.is_ident("unique_by");
+                attrs.key = Some(Key { member, ty });
+                Ok(())
+            } else if meta.path.is_ident("on_duplicate") {
+                if attrs.on_duplicate.is_some() {
+                    return Err(meta.error("duplicate on_duplicate"));
+                }
+                attrs.on_duplicate = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("export") {
+                if attrs.export.is_some() {
+                    return Err(meta.error("duplicate export"));
+                }
+                if !input.generics.params.is_empty() {
+                    return Err(meta.error("export is not supported for generic types"));
+                }
+                attrs.export = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("ffi") {
+                if attrs.ffi.is_some() {
+                    return Err(meta.error("duplicate ffi"));
+                }
+                if !input.generics.params.is_empty() {
+                    return Err(meta.error("ffi is not supported for generic types"));
+                }
+                attrs.ffi = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("crate") {
+                if attrs.krate.is_some() {
+                    return Err(meta.error("duplicate crate"));
+                }
+                attrs.krate = Some(meta.value()?.call(Path::parse_mod_style)?);
+                Ok(())
+            } else if meta.path.is_ident("order") {
+                if attrs.order.is_some() {
+                    return Err(meta.error("duplicate order"));
+                }
+                let member: Member = meta.value()?.parse()?;
+                if field_type(input, &member).is_none() {
+                    return Err(Error::new_spanned(member, "no such field"));
+                }
+                attrs.order = Some(member);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported inventory attribute"))
+            }
+        })?;
+    }
+    if let Some(policy) = &attrs.on_duplicate {
+        if !attrs.unique {
+            let msg = "on_duplicate requires #[inventory(unique_by = ...)]";
+            return Err(Error::new_spanned(policy, msg));
+        }
+    }
+    Ok(attrs)
+}
+
+fn field_type<'a>(input: &'a DeriveInput, member: &Member) -> Option<&'a Type> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        Data::Enum(_) | Data::Union(_) => return None,
+    };
+    match (fields, member) {
+        (Fields::Named(fields), Member::Named(name)) => fields
+            .named
+            .iter()
+            .find(|field| field.ident.as_ref() == Some(name))
+            .map(|field| &field.ty),
+        (Fields::Unnamed(fields), Member::Unnamed(index)) => fields
+            .unnamed
+            .iter()
+            .nth(index.index as usize)
+            .map(|field| &field.ty),
+        _ => None,
+    }
+}
@@ -0,0 +1,179 @@
+use crate::args::Args;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    Attribute, Error, Expr, ExprLit, Ident, Item, Lit, LitStr, Meta, PathArguments, Result, Type,
+};
+
+enum Kind {
+    Fn,
+    Static,
+    Impl,
+}
+
+pub(crate) fn register(args: &Args, input: &Item) -> Result<TokenStream> {
+    let (kind, ident, item, attrs) = match input {
+        Item::Fn(item) => {
+            if !item.sig.generics.params.is_empty() {
+                let msg = "#[inventory::register] is not supported on generic functions";
+                return Err(Error::new_spanned(&item.sig.generics, msg));
+            }
+            let ident = &item.sig.ident;
+            (
+                Kind::Fn,
+                ident.clone(),
+                ident.to_token_stream(),
+                &item.attrs,
+            )
+        }
+        Item::Static(item) => {
+            let ident = &item.ident;
+            (Kind::Static, ident.clone(), quote!(&#ident), &item.attrs)
+        }
+        Item::Impl(item) => {
+            if !item.generics.params.is_empty() {
+                let msg = "#[inventory::register] is not supported on generic impl blocks";
+                return Err(Error::new_spanned(&item.generics, msg));
+            }
+            let ident = match &*item.self_ty {
+                Type::Path(ty)
+                    if ty.qself.is_none()
+                        && ty.path.segments.iter().all(|seg| seg.arguments.is_none()) =>
+                {
+                    ty.path.segments.last().unwrap().ident.clone()
+                }
+                _ => {
+                    let msg = "expected the impl block of a unit struct";
+                    return Err(Error::new_spanned(&item.self_ty, msg));
+                }
+            };
+            let self_ty = &item.self_ty;
+            (Kind::Impl, ident, self_ty.to_token_stream(), &item.attrs)
+        }
+        _ => {
+            let msg = "#[inventory::register] is supported on functions, statics and impl blocks";
+            return Err(Error::new(Span::call_site(), msg));
+        }
+    };
+
+    let krate = args
+        .krate
+        .as_ref()
+        .map_or_else(|| quote!(::inventory), ToTokens::to_token_stream);
+    let ty = &args.ty;
+    let submission = if let Type::TraitObject(_) = ty {
+        if let Some(field) = args.fields.first() {
+            let msg = "fields cannot be given when registering a trait object";
+            return Err(Error::new_spanned(&field.member, msg));
+        }
+        match kind {
+            Kind::Static => quote!(#krate::submit!(#item as &#ty);),
+            Kind::Fn | Kind::Impl => quote!(#krate::submit!(#item as #ty);),
+        }
+    } else if args.fields.is_empty() && matches!(kind, Kind::Static) {
+        quote! {
+            #krate::__do_submit! {
+                used={ #[used] }
+                priority={ 0 }
+                deps=[]
+                value={ &#krate::__private::Static::<#ty> { value: #item } }
+            }
+        }
+    } else if args.fields.is_empty() && matches!(kind, Kind::Impl) {
+        let msg = "expected fields to initialize, as in #[inventory::register(Command, name, run)]";
+        return Err(Error::new_spanned(ty, msg));
+    } else {
+        let fields = fields(args, &kind, &ident, &item, attrs);
+        let path = struct_path(ty)?;
+        quote! {
+            #krate::submit! {
+                #path { #(#fields,)* }
+            }
+        }
+    };
+
+    Ok(quote! {
+        #input
+        #submission
+    })
+}
+
+// Initializers of the fields of the plugin.
+fn fields(
+    args: &Args,
+    kind: &Kind,
+    ident: &Ident,
+    item: &TokenStream,
+    attrs: &[Attribute],
+) -> Vec<TokenStream> {
+    let name = LitStr::new(&ident.to_string(), ident.span());
+    let doc = LitStr::new(&doc(attrs), Span::call_site());
+    let mut fields: Vec<TokenStream> = args
+        .fields
+        .iter()
+        .map(|field| {
+            let member = &field.member;
+            let value = match &field.value {
+                Some(value) => value.to_token_stream(),
+                None if member == "name" => name.to_token_stream(),
+                None if member == "doc" => doc.to_token_stream(),
+                None => item.clone(),
+            };
+            quote!(#member: #value)
+        })
+        .collect();
+    // A function goes into the field `run` unless it is listed under some
+    // other name.
+    if let Kind::Fn = kind {
+        let listed = args.fields.iter().any(|field| {
+            field.member == "run"
+                || field.value.is_none() && field.member != "name" && field.member != "doc"
+        });
+        if !listed {
+            fields.push(quote!(run: #item));
+        }
+    }
+    fields
+}
+
+// The plugin type as a path that can begin a struct expression, which requires
+// generic arguments to be written with turbofish, as in `Command::<T> {}`.
+fn struct_path(ty: &Type) -> Result<TokenStream> {
+    let mut path = match ty {
+        Type::Path(ty) if ty.qself.is_none() => ty.path.clone(),
+        _ => {
+            let msg = "expected the path of a struct type";
+            return Err(Error::new_spanned(ty, msg));
+        }
+    };
+    for segment in &mut path.segments {
+        if let PathArguments::AngleBracketed(arguments) = &mut segment.arguments {
+            arguments.colon2_token.get_or_insert_with(Default::default);
+        }
+    }
+    Ok(path.into_token_stream())
+}
+
+// Contents of the item's doc comments, with the leading space of each line
+// removed, as rustdoc would render them.
+fn doc(attrs: &[Attribute]) -> String {
+    let mut doc = String::new();
+    for attr in attrs {
+        if !attr.path().is_ident("doc") {
+            continue;
+        }
+        if let Meta::NameValue(meta) = &attr.meta {
+            if let Expr::Lit(ExprLit {
+                lit: Lit::Str(lit), ..
+            }) = &meta.value
+            {
+                if !doc.is_empty() {
+                    doc.push('\n');
+                }
+                let line = lit.value();
+                doc.push_str(line.strip_prefix(' ').unwrap_or(&line));
+            }
+        }
+    }
+    doc
+}
@@ -0,0 +1,29 @@
+//! Implementation detail of the `inventory` crate, providing the
+//! `#[inventory::register]` attribute and `#[derive(inventory::Collect)]`.
+//! Enable the `"macros"` feature of `inventory` to use them.
+
+extern crate proc_macro;
+
+mod args;
+mod derive;
+mod expand;
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+#[proc_macro_attribute]
+pub fn register(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as args::Args);
+    let input = parse_macro_input!(input as syn::Item);
+    expand::register(&args, &input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[proc_macro_derive(Collect, attributes(inventory))]
+pub fn derive_collect(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    derive::derive(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,124 @@
+use crate::{Collect, Element, Elements, Guard};
+use core::sync::atomic::Ordering;
+
+/// Turn off plugins of type `T` for which the predicate returns true.
+///
+/// Disabled plugins are left out by [`inventory::iter`][crate::iter] and every
+/// other way of iterating or looking up plugins, except [`iter_all`]. They stay
+/// in the registry and can be turned back on by [`enable`]. Returns the number
+/// of plugins that matched, whether or not they were already disabled.
+///
+/// Whether a plugin is enabled is a property of the submission that produced
+/// it, so all the plugins of one `submit!([...])` or [`submit_all!`] are
+/// disabled together when any one of them matches.
+///
+/// # Examples
+///
+/// ```
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag);
+///
+/// inventory::submit!(Flag { short: 'v', name: "verbose" });
+/// inventory::submit!(Flag { short: 'q', name: "quiet" });
+///
+/// fn main() {
+///     let disabled = ["quiet"];
+///     inventory::disable::<Flag>(|flag| disabled.contains(&flag.name));
+///
+///     let names: Vec<&str> = inventory::iter::<Flag>
+///         .into_iter()
+///         .map(|flag| flag.name)
+///         .collect();
+///     assert_eq!(names, ["verbose"]);
+///     assert_eq!(inventory::iter_all::<Flag>().count(), 2);
+/// }
+/// ```
+///
+/// [`submit_all!`]: crate::submit_all
+pub fn disable<T: ?Sized + Collect>(predicate: impl FnMut(&T) -> bool) -> usize {
+    set_enabled::<T>(predicate, false)
+}
+
+/// Turn back on plugins of type `T`, previously turned off by [`disable`], for
+/// which the predicate returns true.
+///
+/// Returns the number of plugins that matched, whether or not they were
+/// disabled.
+pub fn enable<T: ?Sized + Collect>(predicate: impl FnMut(&T) -> bool) -> usize {
+    set_enabled::<T>(predicate, true)
+}
+
+fn set_enabled<T: ?Sized + Collect>(mut predicate: impl FnMut(&T) -> bool, enabled: bool) -> usize {
+    let registry = T::registry();
+    let _guard = Guard::new(registry);
+    let mut matched = 0;
+    let mut changed = false;
+    let mut element = Element::first(registry.head());
+    while let Some(current) = element {
+        element = current.next();
+        if predicate(unsafe { current.get::<T>() }) {
+            matched += 1;
+            changed |= current.node.enabled.swap(enabled, Ordering::Relaxed) != enabled;
+        }
+    }
+    // Data derived from the enabled plugins, including the index used by
+    // inventory::get, is rebuilt when the generation changes.
+    if changed {
+        registry.generation.fetch_add(1, Ordering::Release);
+    }
+    matched
+}
+
+/// Iterate over plugins registered of a given type, including ones that have
+/// been [disabled][disable].
+///
+/// Plugins are visited in the same order as by
+/// [`inventory::iter`][crate::iter].
+///
+/// # Examples
+///
+/// ```
+/// # struct Flag {
+/// #     short: char,
+/// #     name: &'static str,
+/// # }
+/// #
+/// # inventory::collect!(Flag);
+/// #
+/// fn print_help() {
+///     for flag in inventory::iter_all::<Flag>() {
+///         println!("-{}, --{}", flag.short, flag.name);
+///     }
+/// }
+/// ```
+pub fn iter_all<T: ?Sized + Collect>() -> IterAll<T> {
+    IterAll {
+        elements: Elements::all(),
+    }
+}
+
+/// Iterator returned by [`iter_all`].
+pub struct IterAll<T: ?Sized + 'static> {
+    elements: Elements<T>,
+}
+
+impl<T: ?Sized + Collect> Iterator for IterAll<T> {
+    type Item = &'static T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        Some(unsafe { element.get::<T>() })
+    }
+}
+
+impl<T: ?Sized> Clone for IterAll<T> {
+    fn clone(&self) -> Self {
+        IterAll {
+            elements: self.elements.clone(),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use crate::{iter_sorted, Collect, Elements, IterSorted};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use core::ptr;
+use std::panic::{self, AssertUnwindSafe};
+
+/// C declarations of the functions exported by a registry declared with
+/// `collect!(Type, ffi = "prefix")`.
+///
+/// The `element` argument is how C spells the plugin type, for example `struct
+/// handler` for a `#[repr(C)]` Rust struct whose C definition is provided
+/// elsewhere, or `void` to leave it opaque. The header is intended to be
+/// written out by a build script that depends on `inventory` as a build
+/// dependency. See [`collect!`][crate::collect#foreign-code] for what each
+/// function does.
+///
+/// # Examples
+///
+/// ```
+/// // build.rs
+/// use std::env;
+/// use std::fs;
+/// use std::path::Path;
+///
+/// # let out_dir = env::temp_dir();
+/// # const IGNORE: &str = stringify! {
+/// let out_dir = env::var_os("OUT_DIR").unwrap();
+/// # };
+/// let header = inventory::c_header("handlers", "struct handler");
+/// fs::write(Path::new(&out_dir).join("handlers.h"), header).unwrap();
+/// ```
+pub fn c_header(prefix: &str, element: &str) -> String {
+    format!(
+        "\
+/* Generated by inventory. */
+
+#ifndef INVENTORY_{guard}_H
+#define INVENTORY_{guard}_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern \"C\" {{
+#endif
+
+typedef struct {prefix}_iter {prefix}_iter;
+
+size_t {prefix}_count(void);
+const {element} *{prefix}_get(size_t index);
+{prefix}_iter *{prefix}_iter_new(void);
+const {element} *{prefix}_iter_next({prefix}_iter *iter);
+void {prefix}_iter_free({prefix}_iter *iter);
+
+#ifdef __cplusplus
+}}
+#endif
+
+#endif
+",
+        guard = prefix.to_ascii_uppercase(),
+        prefix = prefix,
+        element = element,
+    )
+}
+
+// Not public API. The bodies of the functions exported by collect!(Type, ffi =
+// "prefix").
+
+// Both go through the view of the registry that is cached until its next
+// generation, so that a C loop calling get for every index takes linear time.
+
+#[doc(hidden)]
+pub fn count<T: Collect>() -> usize {
+    catch(0, || Elements::<T>::new().view().elements.len())
+}
+
+#[doc(hidden)]
+pub fn get<T: Collect>(index: usize) -> *const T {
+    catch(ptr::null(), || {
+        let view = Elements::<T>::new().view();
+        match view.elements.get(index) {
+            Some(element) => unsafe { element.get::<T>() },
+            None => ptr::null(),
+        }
+    })
+}
+
+#[doc(hidden)]
+pub fn iter_new<T: Collect>() -> *mut IterSorted<T> {
+    catch(ptr::null_mut(), || {
+        Box::into_raw(Box::new(iter_sorted::<T>()))
+    })
+}
+
+// SAFETY: requires a pointer returned by iter_new that has not been freed.
+#[doc(hidden)]
+pub unsafe fn iter_next<T: Collect>(iter: *mut IterSorted<T>) -> *const T {
+    let iter = unsafe { &mut *iter };
+    catch(ptr::null(), || match iter.next() {
+        Some(value) => value,
+        None => ptr::null(),
+    })
+}
+
+// SAFETY: requires null or a pointer returned by iter_new that has not been
+// freed.
+#[doc(hidden)]
+pub unsafe fn iter_free<T: Collect>(iter: *mut IterSorted<T>) {
+    if !iter.is_null() {
+        drop(unsafe { Box::from_raw(iter) });
+    }
+}
+
+// Unwinding out of an extern "C" function is undefined behavior, or an abort
+// on newer compilers, so a panic while reading the registry, such as for a
+// duplicate key under OnDuplicate::Panic or from a submit_lazy! initializer,
+// is reported to foreign code as 0 or null instead.
+fn catch<R>(default: R, f: impl FnOnce() -> R) -> R {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or(default)
+}
@@ -0,0 +1,119 @@
+use crate::{iter_sorted, Collect, IterSorted};
+use core::marker::PhantomData;
+
+/// A counter that changes whenever a plugin is entered into the registry of
+/// type `T`.
+///
+/// The generation starts out at the number of plugins submitted before it is
+/// first read, and increases by one every time a node gets linked into the
+/// registry afterward, whether by [`register`][fn@crate::register] or by the
+/// constructors of a dynamically loaded library. It also increases when
+/// [`disable`][crate::disable] or [`enable`][crate::enable] changes which
+/// plugins are enabled. Data derived from iterating the registry remains up to
+/// date as long as the generation is the same as it was before iterating.
+///
+/// See [`Snapshot`] for a cache built on this.
+///
+/// # Examples
+///
+/// ```
+/// # struct Flag {
+/// #     short: char,
+/// #     name: &'static str,
+/// # }
+/// #
+/// # inventory::collect!(Flag);
+/// #
+/// static LATE: inventory::Entry<Flag> = inventory::Entry::new(&Flag {
+///     short: 'l',
+///     name: "late",
+/// });
+///
+/// fn main() {
+///     let before = inventory::generation::<Flag>();
+///     inventory::register(&LATE);
+///     assert_ne!(inventory::generation::<Flag>(), before);
+/// }
+/// ```
+pub fn generation<T: ?Sized + Collect>() -> u64 {
+    T::registry().generation() as u64
+}
+
+/// Derived state computed from the plugins of type `T`, recomputed only when
+/// more plugins have been registered since it was last computed.
+///
+/// # Examples
+///
+/// ```
+/// use inventory::{IterSorted, Snapshot};
+/// use std::collections::BTreeMap;
+/// use std::sync::Mutex;
+///
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag);
+///
+/// inventory::submit!(Flag { short: 'v', name: "verbose" });
+///
+/// fn by_short(flags: IterSorted<Flag>) -> BTreeMap<char, &'static Flag> {
+///     flags.map(|flag| (flag.short, flag)).collect()
+/// }
+///
+/// static BY_SHORT: Mutex<Snapshot<Flag, BTreeMap<char, &Flag>>> =
+///     Mutex::new(Snapshot::new(by_short));
+///
+/// fn lookup(short: char) -> Option<&'static Flag> {
+///     BY_SHORT.lock().unwrap().get().get(&short).copied()
+/// }
+///
+/// static QUIET: inventory::Entry<Flag> = inventory::Entry::new(&Flag {
+///     short: 'q',
+///     name: "quiet",
+/// });
+///
+/// fn main() {
+///     assert_eq!(lookup('v').unwrap().name, "verbose");
+///     assert!(lookup('q').is_none());
+///
+///     inventory::register(&QUIET);
+///     assert_eq!(lookup('q').unwrap().name, "quiet");
+/// }
+/// ```
+pub struct Snapshot<T: ?Sized + 'static, S> {
+    build: fn(IterSorted<T>) -> S,
+    state: Option<(u64, S)>,
+    marker: PhantomData<fn() -> &'static T>,
+}
+
+impl<T: ?Sized + Collect, S> Snapshot<T, S> {
+    /// Create a snapshot that computes its state by passing the plugins of
+    /// type `T` to `build`. Nothing is computed until the first call to
+    /// [`get`][Snapshot::get].
+    pub const fn new(build: fn(IterSorted<T>) -> S) -> Self {
+        Snapshot {
+            build,
+            state: None,
+            marker: PhantomData,
+        }
+    }
+
+    /// The state computed from the plugins currently in the registry,
+    /// rebuilding it first if the [generation][generation()] has changed.
+    pub fn get(&mut self) -> &S {
+        let generation = generation::<T>();
+        let state = match self.state.take() {
+            Some((built, state)) if built == generation => state,
+            _ => (self.build)(iter_sorted::<T>()),
+        };
+        &self.state.insert((generation, state)).1
+    }
+
+    /// Discard the computed state, so that the next call to
+    /// [`get`][Snapshot::get] rebuilds it.
+    pub fn invalidate(&mut self) {
+        self.state = None;
+    }
+}
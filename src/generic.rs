@@ -0,0 +1,76 @@
+use crate::Registry;
+use alloc::boxed::Box;
+use core::any::TypeId;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+// Not public API. Used by generated code.
+//
+// The registries of all instantiations of a generic plugin type declared by
+// `collect!(impl<...> for ...)`. A static inside of a generic function is
+// shared by every instantiation of the function, so each type's registry is
+// allocated the first time it is needed and then found by TypeId in a list
+// that is only ever prepended to.
+#[doc(hidden)]
+pub struct GenericRegistry {
+    head: AtomicPtr<Slot>,
+}
+
+struct Slot {
+    type_id: TypeId,
+    registry: Registry,
+    next: *mut Slot,
+}
+
+impl GenericRegistry {
+    pub const fn new() -> Self {
+        GenericRegistry {
+            head: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    pub fn get<T: ?Sized + 'static>(&'static self) -> &'static Registry {
+        let type_id = TypeId::of::<T>();
+        let mut head = self.head.load(Ordering::Acquire);
+        if let Some(registry) = find(head, ptr::null_mut(), type_id) {
+            return registry;
+        }
+
+        let new = Box::into_raw(Box::new(Slot {
+            type_id,
+            registry: Registry::new(),
+            next: head,
+        }));
+        loop {
+            match self
+                .head
+                .compare_exchange(head, new, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return unsafe { &(*new).registry },
+                Err(current) => {
+                    // Only the slots prepended since `head` was loaded can be
+                    // for the same type.
+                    if let Some(registry) = find(current, head, type_id) {
+                        drop(unsafe { Box::from_raw(new) });
+                        return registry;
+                    }
+                    unsafe { (*new).next = current };
+                    head = current;
+                }
+            }
+        }
+    }
+}
+
+// Search the slots from `slot` up to but not including `end`.
+fn find(mut slot: *mut Slot, end: *mut Slot, type_id: TypeId) -> Option<&'static Registry> {
+    while slot != end {
+        // Slots are never freed once they are linked into the list.
+        let current = unsafe { &*slot };
+        if current.type_id == type_id {
+            return Some(&current.registry);
+        }
+        slot = current.next;
+    }
+    None
+}
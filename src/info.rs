@@ -0,0 +1,127 @@
+use crate::{Collect, Elements};
+use core::fmt::{self, Display};
+
+/// Where a plugin was registered.
+///
+/// For plugins from [`submit!`][crate::submit], this is the location of the
+/// `submit!` invocation. For plugins registered at runtime through
+/// [`register`][fn@crate::register], there is no such location and every field is
+/// empty or zero, except that `register_boxed` records the file, line and
+/// column of its caller.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SubmissionInfo {
+    module_path: &'static str,
+    file: &'static str,
+    line: u32,
+    column: u32,
+}
+
+impl SubmissionInfo {
+    // Not public API. Used by generated code.
+    #[doc(hidden)]
+    pub const fn new(
+        module_path: &'static str,
+        file: &'static str,
+        line: u32,
+        column: u32,
+    ) -> Self {
+        SubmissionInfo {
+            module_path,
+            file,
+            line,
+            column,
+        }
+    }
+
+    pub(crate) const UNKNOWN: Self = SubmissionInfo::new("", "", 0, 0);
+
+    /// Name of the crate containing the `submit!`, as it would be written in a
+    /// path. For example `my_plugin` for a crate named `my-plugin`.
+    pub fn crate_name(&self) -> &'static str {
+        match self.module_path.find("::") {
+            Some(end) => &self.module_path[..end],
+            None => self.module_path,
+        }
+    }
+
+    /// Module path of the `submit!`, as given by `module_path!()`.
+    pub fn module_path(&self) -> &'static str {
+        self.module_path
+    }
+
+    /// Source file of the `submit!`, as given by `file!()`.
+    pub fn file(&self) -> &'static str {
+        self.file
+    }
+
+    /// Line of the `submit!`, as given by `line!()`.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// Column of the `submit!`, as given by `column!()`.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+}
+
+/// Formats as `file:line:column`, like a compiler diagnostic.
+impl Display for SubmissionInfo {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// Iterate over plugins registered of a given type, together with where each
+/// one was registered.
+///
+/// Plugins are visited in the same order as by
+/// [`inventory::iter`][crate::iter].
+///
+/// # Examples
+///
+/// ```
+/// # struct Flag {
+/// #     short: char,
+/// #     name: &'static str,
+/// # }
+/// #
+/// # inventory::collect!(Flag);
+/// #
+/// fn find_duplicates() {
+///     for (flag, info) in inventory::iter_with_meta::<Flag>() {
+///         for (other, other_info) in inventory::iter_with_meta::<Flag>() {
+///             if flag.name == other.name && info != other_info {
+///                 eprintln!("--{} is registered at {} and at {}", flag.name, info, other_info);
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub fn iter_with_meta<T: ?Sized + Collect>() -> IterWithMeta<T> {
+    IterWithMeta {
+        elements: Elements::new(),
+    }
+}
+
+/// Iterator returned by [`iter_with_meta`].
+pub struct IterWithMeta<T: ?Sized + 'static> {
+    elements: Elements<T>,
+}
+
+impl<T: ?Sized + Collect> Iterator for IterWithMeta<T> {
+    type Item = (&'static T, &'static SubmissionInfo);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        Some((unsafe { element.get::<T>() }, &element.node.info))
+    }
+}
+
+impl<T: ?Sized> Clone for IterWithMeta<T> {
+    fn clone(&self) -> Self {
+        IterWithMeta {
+            elements: self.elements.clone(),
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use crate::Collect;
+
+/// Trait bound corresponding to plugin types that can be looked up by key
+/// using [`inventory::get`][get].
+///
+/// Usually implemented by passing a `key` to the [`collect`][crate::collect]
+/// macro, as in `inventory::collect!(Flag, key = name: str)`.
+pub trait Keyed: Collect {
+    /// Type of the key by which plugins are looked up.
+    type Key: ?Sized + Ord;
+
+    /// The key of this plugin.
+    fn key(&self) -> &Self::Key;
+}
+
+/// Find the plugin of type `T` with the given key.
+///
+/// If more than one plugin has the same key, the one visited first by
+/// [`iter_sorted`][crate::iter_sorted] is returned. Plugins turned off by
+/// [`disable`][crate::disable] are not found.
+///
+/// With the `"alloc"` feature, the first call builds a sorted index of all
+/// plugins of type `T`, which subsequent lookups binary search in
+/// *O*(log *n*) time. The index is rebuilt if more plugins are registered
+/// afterward. Without `"alloc"`, every lookup is a linear scan.
+///
+/// # Examples
+///
+/// ```
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag, key = name: str);
+///
+/// inventory::submit! {
+///     Flag { short: 'v', name: "verbose" }
+/// }
+///
+/// fn main() {
+///     let flag = inventory::get::<Flag>("verbose").unwrap();
+///     assert_eq!(flag.short, 'v');
+///
+///     assert!(inventory::get::<Flag>("quiet").is_none());
+/// }
+/// ```
+pub fn get<T: ?Sized + Keyed>(key: &T::Key) -> Option<&'static T> {
+    #[cfg(feature = "alloc")]
+    {
+        index::get(key)
+    }
+
+    #[cfg(not(feature = "alloc"))]
+    {
+        let mut found: Option<crate::Element> = None;
+        for element in crate::Elements::<T>::new() {
+            if unsafe { element.get::<T>() }.key() == key
+                && found.map_or(true, |found| {
+                    crate::Elements::<T>::cmp(element, found) == core::cmp::Ordering::Less
+                })
+            {
+                found = Some(element);
+            }
+        }
+        found.map(|element| unsafe { element.get::<T>() })
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod index {
+    use super::Keyed;
+    use crate::{Element, Elements, Guard};
+    use alloc::vec::Vec;
+
+    pub(super) fn get<T: ?Sized + Keyed>(key: &T::Key) -> Option<&'static T> {
+        // The index refers to plugins that may belong to a shared library.
+        let registry = T::registry();
+        let _guard = Guard::new(registry);
+        let index = registry.index.get(registry, || {
+            // Among plugins with equal keys, the first one in the order of
+            // iter_sorted comes first.
+            let mut elements: Vec<Element> = Elements::<T>::new().collect();
+            elements.sort_unstable_by(|&a, &b| {
+                unsafe { a.get::<T>().key().cmp(b.get::<T>().key()) }
+                    .then_with(|| Elements::<T>::cmp(a, b))
+            });
+            elements
+        });
+
+        let plugins = &index.elements;
+        let i = plugins.partition_point(|element| unsafe { element.get::<T>() }.key() < key);
+        let plugin = unsafe { plugins.get(i)?.get::<T>() };
+        (plugin.key() == key).then_some(plugin)
+    }
+}
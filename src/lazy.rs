@@ -0,0 +1,46 @@
+use crate::once::Once;
+use crate::{Collect, ErasedNode, Registry};
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+
+// Not public API. Used by generated code.
+//
+// Storage for the value of a submit_lazy! invocation. Lives in a static, so the
+// value is never dropped.
+#[doc(hidden)]
+pub struct Lazy<T> {
+    once: Once,
+    value: UnsafeCell<MaybeUninit<T>>,
+    init: fn() -> T,
+}
+
+// The value is only written inside of call_once, which happens-before every
+// read. Since a Lazy is never dropped, T does not need to be Send.
+unsafe impl<T: Sync> Sync for Lazy<T> {}
+
+impl<T> Lazy<T> {
+    pub const fn new(init: fn() -> T) -> Self {
+        Lazy {
+            once: Once::new(),
+            value: UnsafeCell::new(MaybeUninit::uninit()),
+            init,
+        }
+    }
+
+    fn get(&self) -> &T {
+        self.once.call_once(|| unsafe {
+            (*self.value.get()).write((self.init)());
+        });
+        unsafe { (*self.value.get()).assume_init_ref() }
+    }
+}
+
+impl<T: Collect> ErasedNode for Lazy<T> {
+    fn registry(&self) -> &'static Registry {
+        T::registry()
+    }
+
+    fn value(&self) -> *const () {
+        (self.get() as *const T).cast()
+    }
+}
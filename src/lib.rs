@@ -99,8 +99,104 @@
 //! }
 //! ```
 //!
-//! There is no guarantee about the order that plugins of the same type are
-//! visited by the iterator. They may be visited in any order.
+//! Plugins are visited in order of decreasing [priority][submit#priority].
+//! There is no guarantee about the order in which plugins of equal priority are
+//! visited, which depends on the order in which the linker laid them out.
+//! [`inventory::iter_sorted`][iter_sorted] visits plugins of equal priority in
+//! order of the source location of their `submit!` instead.
+//!
+//! ## Linker sections
+//!
+//! By default every `submit!` produces a static constructor which registers the
+//! submitted element during life-before-main. On ELF targets (Linux, Android,
+//! the BSDs, and others) the `"section"` Cargo feature replaces these
+//! constructors with a pointer to each element placed in a link section named
+//! `inventory_0_3`, after the semver-major version of this crate. Nothing runs
+//! before `main`; instead the section, whose bounds are provided by the linker
+//! as `__start_inventory_0_3` and `__stop_inventory_0_3`, is walked the first
+//! time that any plugin registry is iterated. This makes registrations visible
+//! in environments where static constructors are disabled or stripped.
+//!
+//! ```toml
+//! [dependencies]
+//! inventory = { version = "0.3", features = ["section"] }
+//! ```
+//!
+//! Elements submitted from within a dynamically loaded library are only
+//! visible through that library's own copy of the section, so this mode is not
+//! suitable for registering plugins across `dlopen`. On targets that are not
+//! ELF, the feature has no effect and constructors are used as usual.
+//!
+//! ## Unloading shared libraries
+//!
+//! Plugins submitted from a shared library remain in their registry after the
+//! library has been unloaded by `dlclose`, where iterating the registry would
+//! read memory that is no longer mapped. On ELF targets, the `"unload"` Cargo
+//! feature makes every `submit!` also produce a destructor, which removes the
+//! element from its registry when the library is unloaded.
+//!
+//! ```toml
+//! [dependencies]
+//! inventory = { version = "0.3", features = ["unload"] }
+//! ```
+//!
+//! Removal is safe with respect to concurrent iteration: the destructor waits
+//! until every iterator that might still visit the element has been dropped.
+//! Consequently no thread may hold an iterator indefinitely while a library
+//! may be unloaded, or `dlclose` does not return. This includes the thread
+//! calling `dlclose`, so unloading a library from inside of an iteration of a
+//! registry that the library submitted to deadlocks. Nothing is removed, and
+//! nothing waited for, when the process exits. References to plugins that are
+//! retained after the iterator which produced them has been dropped are not
+//! protected, and must not be used once their library is unloaded. The feature
+//! has no effect on elements registered by [`register`][fn@register] or in
+//! `"section"` mode.
+//!
+//! ## Manifest
+//!
+//! On ELF targets, the `"manifest"` Cargo feature makes every `submit!` also
+//! place a record describing the submission in a link section named
+//! `inventory_manifest`. The records are not used at runtime. They let tools
+//! list the plugins registered in a compiled binary or shared library by
+//! reading the section from the file on disk, without running it.
+//!
+//! ```toml
+//! [dependencies]
+//! inventory = { version = "0.3", features = ["manifest"] }
+//! ```
+//!
+//! The section is a sequence of records laid out back to back, with no
+//! alignment. A reader should skip any zero bytes in between records, which a
+//! linker is permitted to insert as padding. Integers are little-endian
+//! regardless of the target, and strings are UTF-8 preceded by their length in
+//! bytes as a `u16`, with no terminator. Each record consists of:
+//!
+//! | Size | Field |
+//! |---|---|
+//! | `u8` | format version, currently 1 |
+//! | `u32` | length in bytes of the whole record, including the version |
+//! | `u32` | line of the `submit!` |
+//! | string | plugin type as written in `collect!`, or the path of the [`NamedRegistry`] for `submit!(in NAME => ...)` |
+//! | string | crate name |
+//! | string | module path |
+//! | string | source file |
+//! | string | label given by `#![label("...")]`, or empty |
+//!
+//! A reader must ignore any bytes in a record after the fields it knows about,
+//! and records with a version that it does not recognize. Each `submit!` of a
+//! slice produces one record, as does each [`submit_lazy!`]. Plugins
+//! registered at runtime by [`register`][fn@register] are not described.
+//!
+//! The `inventory-dump` command-line tool, from the crate of the same name,
+//! prints the records in a binary as a table or as JSON.
+//!
+//! ```console
+//! $ inventory-dump target/release/server
+//! REGISTRY  CRATE   MODULE          LOCATION          LABEL
+//! Flag      server  server::cli     src/cli.rs:12     verbose output
+//! Flag      server  server::cli     src/cli.rs:17
+//! Handler   server  server::routes  src/routes.rs:40
+//! ```
 //!
 //! ## WebAssembly and constructors
 //!
@@ -149,6 +245,7 @@
 
 #![doc(html_root_url = "https://docs.rs/inventory/0.3.24")]
 #![no_std]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 #![deny(unsafe_op_in_unsafe_fn)]
 #![allow(
     clippy::doc_markdown,
@@ -161,45 +258,472 @@
     clippy::semicolon_if_nothing_returned, // https://github.com/rust-lang/rust-clippy/issues/7324
 )]
 
-use core::cell::UnsafeCell;
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(any(feature = "ffi", feature = "test-util"))]
+extern crate std;
+
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::cmp;
 use core::marker::PhantomData;
 use core::ops::Deref;
 use core::ptr;
-#[cfg(target_family = "wasm")]
-use core::sync::atomic::AtomicBool;
-use core::sync::atomic::{AtomicPtr, Ordering};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+use crate::unique::Unique;
+#[cfg(feature = "unload")]
+use crate::unload::Guard;
+#[cfg(feature = "alloc")]
+use crate::view::View;
+
+#[cfg(all(
+    feature = "section",
+    not(target_family = "wasm"),
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "haiku",
+        target_os = "illumos",
+        target_os = "netbsd",
+        target_os = "nto",
+        target_os = "openbsd",
+        target_os = "vxworks",
+        target_os = "none",
+    ),
+))]
+mod section;
+
+mod enable;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod generation;
+#[cfg(feature = "alloc")]
+mod generic;
+mod info;
+mod key;
+mod lazy;
+#[cfg(feature = "manifest")]
+mod manifest;
+mod named;
+mod once;
+#[cfg(feature = "alloc")]
+mod ordered;
+mod registries;
+mod sorted;
+#[cfg(feature = "alloc")]
+mod subscribe;
+#[cfg(feature = "test-util")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-util")))]
+pub mod test;
+mod unique;
+#[cfg(feature = "unload")]
+mod unload;
+#[cfg(feature = "alloc")]
+mod view;
+
+pub use crate::enable::{disable, enable, iter_all, IterAll};
+#[cfg(feature = "ffi")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ffi")))]
+pub use crate::ffi::c_header;
+pub use crate::generation::{generation, Snapshot};
+pub use crate::info::{iter_with_meta, IterWithMeta, SubmissionInfo};
+pub use crate::key::{get, Keyed};
+pub use crate::named::{iter_in, IterIn, NamedRegistry};
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub use crate::ordered::{iter_ordered, IterOrdered, OrderError};
+pub use crate::registries::{registries, RegistryInfo};
+pub use crate::sorted::{iter_sorted, IterSorted};
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+pub use crate::subscribe::subscribe;
+pub use crate::unique::{try_iter, DuplicateError, OnDuplicate};
+
+/// Enter an element built from the annotated item into a plugin registry.
+///
+/// The first argument is the plugin type. It is followed by the fields of the
+/// plugin to submit, each either `field = value` or just `field`. A field
+/// given without a value is filled in from the annotated item:
+///
+/// - `name` is the name of the item, as a string literal;
+/// - `doc` is the item's doc comments, as a string literal;
+/// - any other field is the item itself: a function pointer for a function,
+///   or a `&'static` reference for a static.
+///
+/// A function that is not listed under any field goes into the field `run`.
+///
+/// ```
+/// pub struct Command {
+///     name: &'static str,
+///     help: &'static str,
+///     run: fn(&[String]),
+/// }
+///
+/// inventory::collect!(Command);
+///
+/// /// Compile the current package.
+/// #[inventory::register(Command, name, help = "build the package", run)]
+/// fn build(args: &[String]) {
+///     /* ... */
+/// }
+///
+/// #[inventory::register(Command, name = "check", help = "check the package")]
+/// fn check(args: &[String]) {
+///     /* ... */
+/// }
+/// ```
+///
+/// Code that refers to the `inventory` crate by a different path, such as a
+/// macro that reexports it, can pass that path as `crate = path` among the
+/// fields.
+///
+/// When the plugin type is the type of an annotated static, no fields are
+/// needed and the static itself is entered into the registry.
+///
+/// When the plugin type is a trait object type `dyn Trait`, the item is
+/// entered into the registry of the trait object, the same as by
+/// `inventory::submit!(item as dyn Trait)`. This is the way to register an
+/// impl block, whose self type must be a unit struct.
+///
+/// ```
+/// pub trait Format: Sync {
+///     fn extension(&self) -> &'static str;
+/// }
+///
+/// inventory::collect!(dyn Format);
+///
+/// pub struct Json;
+///
+/// #[inventory::register(dyn Format)]
+/// impl Format for Json {
+///     fn extension(&self) -> &'static str {
+///         "json"
+///     }
+/// }
+/// ```
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use inventory_impl::register;
+
+/// Derive macro generating an impl of the trait `Collect`, as an alternative to
+/// the [`collect!`] macro.
+///
+/// The registry is configured by `#[inventory(...)]` attributes on the type:
+///
+/// - `#[inventory(key = field)]` implements [`Keyed`], so that plugins can be
+///   looked up using [`inventory::get`][get]. If the field is a reference
+///   `&'static K`, the key type is `K`. A different key type may be given as
+///   `key = field: Type`, where `&self.field` must coerce to `&Type`.
+///
+/// - `#[inventory(unique_by = field)]` additionally checks that no two plugins
+///   have the same key, as described in [`collect!`][collect#unique-keys]. It
+///   may be followed by `on_duplicate = ...` with a variant of
+///   [`OnDuplicate`].
+///
+/// - `#[inventory(order = field)]` makes iteration visit plugins in ascending
+///   order of the value of the given field, instead of by priority and source
+///   location. Plugins with equal values are visited in the usual order. With
+///   the `"alloc"` feature the plugins are sorted once after each new
+///   registration; without it, every step of iteration searches the registry
+///   for the next plugin, which makes a full iteration take *O*(*n*²) time.
+///
+/// - `#[inventory(export = "symbol")]` exports the registry under the given
+///   symbol name, for dynamically loaded libraries to join, as described in
+///   [`collect!`][collect#dynamically-loaded-libraries].
+///
+/// - `#[inventory(ffi = "prefix")]` exports functions for reading the registry
+///   from other languages, as described in [`collect!`][collect#foreign-code].
+///   This requires the `"ffi"` feature.
+///
+/// - `#[inventory(crate = path)]` names the path of the `inventory` crate, for
+///   code that refers to it by a different path, such as a macro that
+///   reexports it.
+///
+/// Generic types get a separate registry for every instantiation, as described
+/// in [`collect!`][collect#generic-types], which requires the `"alloc"`
+/// feature.
+///
+/// ```
+/// #[derive(inventory::Collect)]
+/// #[inventory(unique_by = name, order = name)]
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::submit!(Flag { short: 'v', name: "verbose" });
+/// inventory::submit!(Flag { short: 'a', name: "all" });
+///
+/// fn main() {
+///     let names: Vec<&str> = inventory::iter::<Flag>
+///         .into_iter()
+///         .map(|flag| flag.name)
+///         .collect();
+///     assert_eq!(names, ["all", "verbose"]);
+///
+///     assert_eq!(inventory::get::<Flag>("verbose").unwrap().short, 'v');
+/// }
+/// ```
+#[cfg(feature = "macros")]
+#[cfg_attr(docsrs, doc(cfg(feature = "macros")))]
+pub use inventory_impl::Collect;
 
 // Not public API. Used by generated code.
 #[doc(hidden)]
 pub struct Registry {
     head: AtomicPtr<Node>,
+    generation: AtomicUsize,
+    checked: AtomicUsize,
+    #[cfg(feature = "alloc")]
+    view: view::Cache,
+    #[cfg(feature = "alloc")]
+    index: view::Cache,
+    #[cfg(feature = "alloc")]
+    subscribers: AtomicPtr<subscribe::Subscriber>,
+    #[cfg(feature = "unload")]
+    epoch: AtomicUsize,
+    #[cfg(feature = "unload")]
+    readers: [AtomicUsize; 2],
+    #[cfg(feature = "unload")]
+    removing: AtomicBool,
 }
 
 // Not public API. Used by generated code.
 #[doc(hidden)]
 pub struct Node {
     pub value: &'static dyn ErasedNode,
-    pub next: UnsafeCell<Option<&'static Node>>,
-    #[cfg(target_family = "wasm")]
+    pub next: AtomicPtr<Node>,
     pub initialized: AtomicBool,
+    pub delivered: AtomicUsize,
+    pub enabled: AtomicBool,
+    pub info: SubmissionInfo,
+    pub priority: i32,
+    pub constraints: &'static Constraints,
+}
+
+// Not public API. Used by generated code.
+//
+// The identifier of a submission, and the identifiers of the submissions that
+// inventory::iter_ordered must visit it after or before. Any number of
+// submissions may share an identifier.
+#[doc(hidden)]
+pub struct Constraints {
+    pub id: &'static str,
+    pub after: &'static [&'static str],
+    pub before: &'static [&'static str],
+}
+
+impl Constraints {
+    pub const NONE: Self = Constraints {
+        id: "",
+        after: &[],
+        before: &[],
+    };
+}
+
+impl Node {
+    // Not public API. Used by generated code.
+    #[doc(hidden)]
+    pub fn submit(&'static self) {
+        #[cfg(feature = "manifest")]
+        manifest::retain();
+
+        #[cfg(feature = "unload")]
+        unload::watch_exit();
+
+        // Safe because the node's value belongs to the registry it names.
+        unsafe { self.value.registry().submit(self) }
+    }
+
+    // Not public API. Used by generated code.
+    #[cfg(feature = "unload")]
+    #[doc(hidden)]
+    pub fn remove(&'static self) {
+        self.value.registry().remove(self);
+    }
+
+    fn next_node(&self) -> Option<&'static Node> {
+        let next = self.next.load(Ordering::Acquire);
+        // The next pointer of a node that is being removed is marked.
+        #[cfg(feature = "unload")]
+        let next = unload::unmark(next);
+        // Next pointer is always null or valid &'static Node.
+        unsafe { next.as_ref() }
+    }
+
+    // Order in which iter_sorted visits nodes: higher priority first, then by
+    // the location of the submit! invocation. Distinct submissions from the
+    // same location, such as from a macro_rules macro that invokes submit!
+    // repeatedly, are distinguished by address.
+    fn order(&self, other: &Node) -> cmp::Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| self.info.cmp(&other.info))
+            .then_with(|| ptr::addr_of!(*self).cmp(&ptr::addr_of!(*other)))
+    }
+}
+
+// Held while traversing a registry, so that nodes of a shared library being
+// unloaded are not removed in the meantime. Without the "unload" feature nodes
+// are never removed, and there is nothing to hold.
+#[cfg(not(feature = "unload"))]
+#[derive(Clone)]
+struct Guard;
+
+#[cfg(not(feature = "unload"))]
+impl Guard {
+    fn new(_registry: &'static Registry) -> Self {
+        Guard
+    }
+}
+
+// One plugin in a registry: a node, and which one of the node's values. Most
+// nodes contribute exactly one value, but a node submitted as a slice
+// contributes one per element of the slice.
+#[derive(Copy, Clone)]
+struct Element {
+    node: &'static Node,
+    index: usize,
 }
 
-// The `value` is Sync, and `next` is only mutated during submit, which is prior
-// to any reads.
-unsafe impl Sync for Node {}
+impl Element {
+    // The first element of `node`, or of the nearest nonempty node after it.
+    fn first(mut node: Option<&'static Node>) -> Option<Self> {
+        while let Some(current) = node {
+            if current.value.count() > 0 {
+                return Some(Element {
+                    node: current,
+                    index: 0,
+                });
+            }
+            node = current.next_node();
+        }
+        None
+    }
+
+    fn next(self) -> Option<Self> {
+        if self.index + 1 < self.node.value.count() {
+            Some(Element {
+                node: self.node,
+                index: self.index + 1,
+            })
+        } else {
+            Element::first(self.node.next_node())
+        }
+    }
+
+    fn is(self, other: Self) -> bool {
+        ptr::eq(self.node, other.node) && self.index == other.index
+    }
+
+    fn order(self, other: Self) -> cmp::Ordering {
+        self.node
+            .order(other.node)
+            .then_with(|| self.index.cmp(&other.index))
+    }
+
+    // SAFETY: requires the values contributed by the node to be of type T.
+    unsafe fn get<T: ?Sized + Collect>(self) -> &'static T {
+        unsafe { T::from_erased(self.node.value.value(), self.index) }
+    }
+}
 
 // Not public API. Used by generated code.
 #[doc(hidden)]
 pub trait ErasedNode: Sync {
-    // SAFETY: requires *node.value is of type Self.
-    unsafe fn submit(&self, node: &'static Node);
+    // The registry into which this node is linked. Every value that the node
+    // contributes must be of the type that the registry is declared for.
+    fn registry(&self) -> &'static Registry;
+
+    // Pointer to the value that this node contributes to the registry of T, in
+    // the representation expected by T::from_erased.
+    fn value(&self) -> *const ();
+
+    // Number of values that this node contributes. If more than one, they are
+    // laid out as an array starting at the pointer returned by value().
+    fn count(&self) -> usize {
+        1
+    }
 }
 
 impl<T: Collect> ErasedNode for T {
-    unsafe fn submit(&self, node: &'static Node) {
-        unsafe {
-            T::registry().submit(node);
-        }
+    fn registry(&self) -> &'static Registry {
+        T::registry()
+    }
+
+    fn value(&self) -> *const () {
+        (self as *const T).cast()
+    }
+}
+
+// Not public API. Used by generated code.
+//
+// The node submitted by #[inventory::register] on a static, which enters the
+// static itself into the registry of its type.
+#[doc(hidden)]
+pub struct Static<T: 'static> {
+    pub value: &'static T,
+}
+
+impl<T: Collect> ErasedNode for Static<T> {
+    fn registry(&self) -> &'static Registry {
+        T::registry()
+    }
+
+    fn value(&self) -> *const () {
+        (self.value as *const T).cast()
+    }
+}
+
+// Not public API. Used by generated code.
+//
+// The node submitted by `submit!([a, b, c])` or `submit_all!(slice)`, which
+// enters every element of the slice into the registry of the element type.
+#[doc(hidden)]
+pub struct Slice<T: 'static> {
+    pub values: &'static [T],
+}
+
+impl<T: Collect> ErasedNode for Slice<T> {
+    fn registry(&self) -> &'static Registry {
+        T::registry()
+    }
+
+    fn value(&self) -> *const () {
+        self.values.as_ptr().cast()
+    }
+
+    fn count(&self) -> usize {
+        self.values.len()
+    }
+}
+
+// Not public API. Used by generated code.
+//
+// The node submitted by `submit!(value as dyn Trait)`. The registry of an
+// unsized type holds fat references, so the erased pointer produced by this
+// node points to the reference, not to the referent.
+#[doc(hidden)]
+pub struct Ref<T: ?Sized + 'static> {
+    pub value: &'static T,
+}
+
+impl<T: ?Sized + Collect> ErasedNode for Ref<T> {
+    fn registry(&self) -> &'static Registry {
+        T::registry()
+    }
+
+    fn value(&self) -> *const () {
+        ptr::addr_of!(self.value).cast()
     }
 }
 
@@ -213,13 +737,255 @@ impl<T: Collect> ErasedNode for T {
 /// ```
 /// use inventory::Collect;
 ///
-/// fn count_plugins<T: Collect>() -> usize {
+/// fn count_plugins<T: Collect + ?Sized>() -> usize {
 ///     inventory::iter::<T>.into_iter().count()
 /// }
 /// ```
-pub trait Collect: Sync + Sized + 'static {
+pub trait Collect: Sync + 'static {
     #[doc(hidden)]
     fn registry() -> &'static Registry;
+
+    // The type as written in collect!, recorded in the manifest.
+    #[doc(hidden)]
+    const NAME: &'static str;
+
+    // SAFETY: requires a pointer obtained from ErasedNode::value of a node in
+    // the registry of Self, and an index less than ErasedNode::count.
+    #[doc(hidden)]
+    unsafe fn from_erased(value: *const (), index: usize) -> &'static Self;
+
+    // The pointer from which from_erased with index 0 produces the given
+    // plugin. For trait objects it points to the reference itself, so it is
+    // valid only for as long as the reference is.
+    #[doc(hidden)]
+    fn erase(value: &&'static Self) -> *const ();
+
+    #[doc(hidden)]
+    const UNIQUE: Option<Unique<Self>> = None;
+
+    #[doc(hidden)]
+    const ORDER: Option<fn(&Self, &Self) -> cmp::Ordering> = None;
+}
+
+// Position of a traversal. The next position is found from the current one
+// only when it is asked for, so that a traversal never refers to a node that it
+// has not reached yet.
+#[derive(Copy, Clone)]
+enum Cursor<P> {
+    Start,
+    At(P),
+    End,
+}
+
+// Walks the elements of the registry of T in iteration order, leaving out any
+// that are excluded by the policy for duplicate keys of a `unique_by` registry,
+// and unless `disabled` is set, any that have been disabled. If `sorted` is
+// set, elements are visited in the order of iter_sorted.
+struct Elements<T: ?Sized + 'static> {
+    guard: Guard,
+    head: Option<&'static Node>,
+    cursor: Cursor<Element>,
+    last: Option<Element>,
+    disabled: bool,
+    sorted: bool,
+    #[cfg(feature = "alloc")]
+    view: Option<Arc<View>>,
+    #[cfg(feature = "alloc")]
+    position: usize,
+    marker: PhantomData<T>,
+}
+
+impl<T: ?Sized + Collect> Elements<T> {
+    fn new() -> Self {
+        let generation = T::registry().generation();
+        let elements = Self::unchecked();
+        unique::check::<T>(generation, elements.head);
+        elements
+    }
+
+    fn unchecked() -> Self {
+        let guard = Guard::new(T::registry());
+        let head = T::registry().head();
+        Elements {
+            guard,
+            head,
+            cursor: Cursor::Start,
+            last: None,
+            disabled: false,
+            sorted: false,
+            #[cfg(feature = "alloc")]
+            view: None,
+            #[cfg(feature = "alloc")]
+            position: 0,
+            marker: PhantomData,
+        }
+    }
+
+    fn all() -> Self {
+        Elements {
+            disabled: true,
+            ..Self::new()
+        }
+    }
+
+    fn sorted() -> Self {
+        Elements {
+            sorted: true,
+            ..Self::new()
+        }
+    }
+
+    fn keep(&self, element: Element) -> bool {
+        (self.disabled || element.node.enabled.load(Ordering::Relaxed))
+            && unique::keep::<T>(self.head, element, self.disabled)
+    }
+
+    // Order in which elements are visited when they are not visited in list
+    // order. For a type declared with an `order`, plugins are visited by
+    // ascending value of the ordering field, with ties broken by the order of
+    // iter_sorted.
+    fn cmp(a: Element, b: Element) -> cmp::Ordering {
+        match T::ORDER {
+            Some(order) => {
+                order(unsafe { a.get::<T>() }, unsafe { b.get::<T>() }).then_with(|| a.order(b))
+            }
+            None => a.order(b),
+        }
+    }
+
+    // Each step scans the whole list for the least element that comes after
+    // the one visited last, so a full iteration takes quadratic time. With
+    // the "alloc" feature, next_viewed is used instead.
+    #[cfg(not(feature = "alloc"))]
+    fn next_ordered(&mut self) -> Option<Element> {
+        let mut least: Option<Element> = None;
+        let mut element = Element::first(self.head);
+        while let Some(current) = element {
+            element = current.next();
+            if self.last.map_or(true, |last| {
+                Self::cmp(current, last) == cmp::Ordering::Greater
+            }) && least.map_or(true, |least| {
+                Self::cmp(current, least) == cmp::Ordering::Less
+            }) && self.keep(current)
+            {
+                least = Some(current);
+            }
+        }
+        self.last = least.or(self.last);
+        least
+    }
+
+    // Whether iteration goes through a view of the registry, computed once
+    // per generation, instead of deciding which element comes next by
+    // scanning the list of nodes at every step.
+    #[cfg(feature = "alloc")]
+    fn viewed(&self) -> bool {
+        self.sorted || T::ORDER.is_some() || T::UNIQUE.as_ref().map_or(false, Unique::excludes)
+    }
+
+    // The elements to visit, in order. Views are always sorted, so that they
+    // can be shared between iter_sorted and any iteration that needs a view.
+    #[cfg(feature = "alloc")]
+    fn build(&self) -> Vec<Element> {
+        let mut elements = Vec::new();
+        let mut element = Element::first(T::registry().head());
+        while let Some(current) = element {
+            element = current.next();
+            elements.push(current);
+        }
+        elements.retain(|element| self.disabled || element.node.enabled.load(Ordering::Relaxed));
+        unique::retain::<T>(&mut elements);
+        elements.sort_unstable_by(|&a, &b| Self::cmp(a, b));
+        elements
+    }
+
+    // The elements to visit as of the current generation of the registry.
+    #[cfg(feature = "alloc")]
+    fn view(&self) -> Arc<View> {
+        let registry = T::registry();
+        // Iteration over all the elements including disabled ones is not
+        // common enough to be worth caching.
+        if self.disabled {
+            Arc::new(View {
+                generation: registry.generation(),
+                elements: self.build(),
+            })
+        } else {
+            registry.view.get(registry, || self.build())
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    fn next_viewed(&mut self) -> Option<Element> {
+        let generation = T::registry().generation();
+        let view = match &self.view {
+            Some(view) if view.generation == generation => view,
+            _ => {
+                let view = self.view();
+                // The registry may have changed since the previous view was
+                // computed. Resume after the element visited last.
+                self.position = match self.last {
+                    Some(last) => view.elements.partition_point(|&element| {
+                        Self::cmp(element, last) != cmp::Ordering::Greater
+                    }),
+                    None => 0,
+                };
+                self.view.insert(view)
+            }
+        };
+        let element = *view.elements.get(self.position)?;
+        self.position += 1;
+        self.last = Some(element);
+        Some(element)
+    }
+}
+
+impl<T: ?Sized + Collect> Iterator for Elements<T> {
+    type Item = Element;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        #[cfg(feature = "alloc")]
+        if self.viewed() {
+            return self.next_viewed();
+        }
+        #[cfg(not(feature = "alloc"))]
+        if self.sorted || T::ORDER.is_some() {
+            return self.next_ordered();
+        }
+        loop {
+            let element = match self.cursor {
+                Cursor::Start => Element::first(T::registry().head()),
+                Cursor::At(element) => element.next(),
+                Cursor::End => None,
+            };
+            let Some(element) = element else {
+                self.cursor = Cursor::End;
+                return None;
+            };
+            self.cursor = Cursor::At(element);
+            if self.keep(element) {
+                return Some(element);
+            }
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for Elements<T> {
+    fn clone(&self) -> Self {
+        Elements {
+            guard: self.guard.clone(),
+            head: self.head,
+            cursor: self.cursor,
+            last: self.last,
+            disabled: self.disabled,
+            sorted: self.sorted,
+            #[cfg(feature = "alloc")]
+            view: self.view.clone(),
+            #[cfg(feature = "alloc")]
+            position: self.position,
+            marker: PhantomData,
+        }
+    }
 }
 
 impl Registry {
@@ -227,6 +993,20 @@ impl Registry {
     pub const fn new() -> Self {
         Registry {
             head: AtomicPtr::new(ptr::null_mut()),
+            generation: AtomicUsize::new(0),
+            checked: AtomicUsize::new(usize::MAX),
+            #[cfg(feature = "alloc")]
+            view: view::Cache::new(),
+            #[cfg(feature = "alloc")]
+            index: view::Cache::new(),
+            #[cfg(feature = "alloc")]
+            subscribers: AtomicPtr::new(ptr::null_mut()),
+            #[cfg(feature = "unload")]
+            epoch: AtomicUsize::new(0),
+            #[cfg(feature = "unload")]
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            #[cfg(feature = "unload")]
+            removing: AtomicBool::new(false),
         }
     }
 
@@ -240,36 +1020,153 @@ impl Registry {
         // only once per instantiation. If this heuristic goes wrong, we can end
         // up having our constructors invoked multiple times, which without this
         // safeguard would lead to our registry's linked list becoming circular.
-        // On non-Wasm platforms, this check is unnecessary, so we skip it.
-        #[cfg(target_family = "wasm")]
+        // The same safeguard makes it harmless to pass one Entry to
+        // inventory::register more than once.
         if new.initialized.swap(true, Ordering::Relaxed) {
             return;
         }
 
-        let mut head = self.head.load(Ordering::Relaxed);
+        // Keep the list in order of decreasing priority. A node goes ahead of
+        // the nodes of equal priority, so in a registry where no submission
+        // sets a priority, which is most of them, every node is linked in at
+        // the head in constant time. Plugins of equal priority stay in the
+        // order in which they were registered. Only iter_sorted orders them
+        // by location, at the time of iteration.
+        //
+        // A failed compare_exchange means that some other node was linked in
+        // after `link` in the meantime, and the search resumes from there, or
+        // with the "unload" feature that the node owning `link` is being
+        // removed, which is handled below. The guard keeps any node reached by
+        // the search from being removed entirely until the search is done.
+        let _guard = Guard::new(self);
+        let new_ptr = ptr::addr_of!(*new).cast_mut();
+        let mut link = &self.head;
         loop {
-            unsafe {
-                *new.next.get() = head.as_ref();
+            let next = link.load(Ordering::Acquire);
+            // No node may be linked in after a node that is being removed, so
+            // search again for a place that is not next to it.
+            #[cfg(feature = "unload")]
+            if unload::is_marked(next) {
+                link = &self.head;
+                continue;
             }
-            let new_ptr = ptr::addr_of!(*new).cast_mut();
-            match self
-                .head
-                .compare_exchange(head, new_ptr, Ordering::Release, Ordering::Relaxed)
-            {
-                Ok(_) => return,
-                Err(prev) => head = prev,
+            match unsafe { next.as_ref() } {
+                Some(node) if node.priority > new.priority => link = &node.next,
+                _ => {
+                    new.next.store(next, Ordering::Relaxed);
+                    if link
+                        .compare_exchange(next, new_ptr, Ordering::Release, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        self.generation.fetch_add(1, Ordering::Release);
+                        #[cfg(feature = "alloc")]
+                        subscribe::notify(self, new);
+                        return;
+                    }
+                }
             }
         }
     }
+
+    fn head(&self) -> Option<&'static Node> {
+        #[cfg(feature = "test-util")]
+        if let Some(substitution) = test::substitution(self) {
+            return substitution.head;
+        }
+        self.linked()
+    }
+
+    // The first node linked into the registry, disregarding any substitution
+    // made by inventory::test on the current thread.
+    fn linked(&self) -> Option<&'static Node> {
+        init();
+        let head = self.head.load(Ordering::Acquire);
+        // Head pointer is always null or valid &'static Node.
+        unsafe { head.as_ref() }
+    }
+
+    // Number of nodes submitted so far.
+    fn generation(&self) -> usize {
+        init();
+        #[cfg(feature = "test-util")]
+        if let Some(substitution) = test::substitution(self) {
+            return substitution.generation;
+        }
+        self.generation.load(Ordering::Acquire)
+    }
 }
 
-/// An iterator over plugins registered of a given type.
+// Link in any nodes that are not submitted by a constructor.
+fn init() {
+    #[cfg(all(
+        feature = "section",
+        not(target_family = "wasm"),
+        any(
+            target_os = "linux",
+            target_os = "android",
+            target_os = "dragonfly",
+            target_os = "freebsd",
+            target_os = "haiku",
+            target_os = "illumos",
+            target_os = "netbsd",
+            target_os = "nto",
+            target_os = "openbsd",
+            target_os = "vxworks",
+            target_os = "none",
+        ),
+    ))]
+    crate::section::init();
+}
+
+/// A node through which a value computed at runtime is entered into the plugin
+/// registry corresponding to its type.
 ///
-/// The value `inventory::iter::<T>` is an iterator with element type `&'static
-/// T`.
+/// Use [`register`][fn@register] to link an `Entry` into its registry, after which the value
+/// is visited by `inventory::iter::<T>` together with all the values from
+/// [`submit!`] invocations.
+pub struct Entry<T: 'static> {
+    node: Node,
+    marker: PhantomData<&'static T>,
+}
+
+impl<T: Collect> Entry<T> {
+    /// Wrap a value for runtime registration.
+    pub const fn new(value: &'static T) -> Self {
+        Self::with_info(value, SubmissionInfo::UNKNOWN)
+    }
+
+    const fn with_info(value: &'static T, info: SubmissionInfo) -> Self {
+        Entry {
+            node: Node {
+                value,
+                next: AtomicPtr::new(ptr::null_mut()),
+                initialized: AtomicBool::new(false),
+                delivered: AtomicUsize::new(0),
+                enabled: AtomicBool::new(true),
+                info,
+                priority: 0,
+                constraints: &Constraints::NONE,
+            },
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Enter an element into the plugin registry corresponding to its type, at
+/// runtime.
 ///
-/// There is no guarantee about the order that plugins of the same type are
-/// visited by the iterator. They may be visited in any order.
+/// Unlike [`submit!`], the value does not need to be a constant expression.
+/// After this call returns, the value is visited by every subsequently created
+/// `inventory::iter::<T>`. Registering the same `Entry` more than once has no
+/// further effect.
+///
+/// This takes an [`Entry`] rather than a bare `&'static T` because a registry
+/// is an intrusive linked list, so every element needs a node of its own to be
+/// linked through, and without the `"alloc"` feature there is nowhere for
+/// `inventory` to put one. [`Entry::new`] is a `const fn`, so any `&'static T`
+/// can be given a node in a static right next to it, as below. With the
+/// `"alloc"` feature, `register_boxed` allocates the node along with the
+/// value.
 ///
 /// # Examples
 ///
@@ -281,30 +1178,97 @@ impl Registry {
 /// #
 /// # inventory::collect!(Flag);
 /// #
-/// # const IGNORE: &str = stringify! {
-/// use my_flags::Flag;
-/// # };
+/// use inventory::Entry;
+///
+/// static QUIET: Flag = Flag { short: 'q', name: "quiet" };
+/// static QUIET_ENTRY: Entry<Flag> = Entry::new(&QUIET);
 ///
 /// fn main() {
-///     for flag in inventory::iter::<Flag> {
-///         println!("-{}, --{}", flag.short, flag.name);
-///     }
+///     inventory::register(&QUIET_ENTRY);
+///
+///     assert!(inventory::iter::<Flag>.into_iter().any(|flag| flag.name == "quiet"));
 /// }
 /// ```
+pub fn register<T: Collect>(entry: &'static Entry<T>) {
+    // Safe because Entry<T>'s node value is a T.
+    unsafe { T::registry().submit(&entry.node) }
+}
+
+/// Enter a heap allocated element into the plugin registry corresponding to its
+/// type, at runtime.
 ///
-/// Refer to the [crate level documentation](index.html) for a complete example
-/// of instantiating a plugin registry and submitting plugins.
-#[allow(non_camel_case_types)]
+/// The value is leaked, as registered elements remain in their registry for the
+/// rest of the program.
+///
+/// # Examples
+///
+/// ```
+/// # struct Flag {
+/// #     short: char,
+/// #     name: String,
+/// # }
+/// #
+/// # inventory::collect!(Flag);
+/// #
+/// fn main() {
+///     let name = std::env::var("EXTRA_FLAG").unwrap_or_else(|_| "extra".to_owned());
+///     inventory::register_boxed(Box::new(Flag { short: 'x', name }));
+/// }
+/// ```
+#[cfg(feature = "alloc")]
+#[cfg_attr(docsrs, doc(cfg(feature = "alloc")))]
+#[track_caller]
+pub fn register_boxed<T: Collect>(value: Box<T>) {
+    let caller = core::panic::Location::caller();
+    let info = SubmissionInfo::new("", caller.file(), caller.line(), caller.column());
+    let value = Box::leak(value);
+    register(Box::leak(Box::new(Entry::with_info(value, info))));
+}
+
+/// An iterator over plugins registered of a given type.
+///
+/// The value `inventory::iter::<T>` is an iterator with element type `&'static
+/// T`.
+///
+/// Plugins are visited in order of decreasing [priority][submit#priority].
+/// Plugins of equal priority may be visited in any order; use [`iter_sorted`]
+/// for an order that does not depend on the linker. Plugins turned off by
+/// [`disable`] are skipped.
+///
+/// # Examples
+///
+/// ```
+/// # struct Flag {
+/// #     short: char,
+/// #     name: &'static str,
+/// # }
+/// #
+/// # inventory::collect!(Flag);
+/// #
+/// # const IGNORE: &str = stringify! {
+/// use my_flags::Flag;
+/// # };
+///
+/// fn main() {
+///     for flag in inventory::iter::<Flag> {
+///         println!("-{}, --{}", flag.short, flag.name);
+///     }
+/// }
+/// ```
+///
+/// Refer to the [crate level documentation](index.html) for a complete example
+/// of instantiating a plugin registry and submitting plugins.
+#[allow(non_camel_case_types)]
 pub type iter<T> = private::iter<T>;
 
 mod void_iter {
     enum Void {}
 
     #[repr(C, packed)]
-    pub struct Iter<T>([*const T; 0], Void);
+    pub struct Iter<T: ?Sized>([*const T; 0], Void);
 
-    unsafe impl<T> Send for Iter<T> {}
-    unsafe impl<T> Sync for Iter<T> {}
+    unsafe impl<T: ?Sized> Send for Iter<T> {}
+    unsafe impl<T: ?Sized> Sync for Iter<T> {}
 }
 
 mod value_iter {
@@ -315,7 +1279,7 @@ mod value_iter {
 mod private {
     // Based on https://github.com/dtolnay/ghost
     #[allow(non_camel_case_types)]
-    pub enum iter<T> {
+    pub enum iter<T: ?Sized> {
         __Phantom(crate::void_iter::Iter<T>),
         iter,
     }
@@ -328,16 +1292,13 @@ mod private {
 pub use crate::private::*;
 
 const _: () = {
-    fn into_iter<T: Collect>() -> Iter<T> {
-        let head = T::registry().head.load(Ordering::Acquire);
+    fn into_iter<T: ?Sized + Collect>() -> Iter<T> {
         Iter {
-            // Head pointer is always null or valid &'static Node.
-            node: unsafe { head.as_ref() },
-            marker: PhantomData,
+            elements: Elements::new(),
         }
     }
 
-    impl<T: Collect> IntoIterator for iter<T> {
+    impl<T: ?Sized + Collect> IntoIterator for iter<T> {
         type Item = &'static T;
         type IntoIter = Iter<T>;
 
@@ -347,36 +1308,30 @@ const _: () = {
     }
 
     #[doc(hidden)]
-    impl<T: Collect> Deref for iter<T> {
+    impl<T: ?Sized + Collect> Deref for iter<T> {
         type Target = fn() -> Iter<T>;
         fn deref(&self) -> &Self::Target {
             &(into_iter as fn() -> Iter<T>)
         }
     }
 
-    pub struct Iter<T: 'static> {
-        node: Option<&'static Node>,
-        marker: PhantomData<T>,
+    pub struct Iter<T: ?Sized + 'static> {
+        elements: Elements<T>,
     }
 
-    impl<T: 'static> Iterator for Iter<T> {
+    impl<T: ?Sized + Collect> Iterator for Iter<T> {
         type Item = &'static T;
 
         fn next(&mut self) -> Option<Self::Item> {
-            let node = self.node?;
-            unsafe {
-                let value_ptr = ptr::addr_of!(*node.value).cast::<T>();
-                self.node = *node.next.get();
-                Some(&*value_ptr)
-            }
+            let element = self.elements.next()?;
+            Some(unsafe { element.get::<T>() })
         }
     }
 
-    impl<T> Clone for Iter<T> {
+    impl<T: ?Sized> Clone for Iter<T> {
         fn clone(&self) -> Self {
             Self {
-                node: self.node,
-                marker: PhantomData,
+                elements: self.elements.clone(),
             }
         }
     }
@@ -411,15 +1366,367 @@ const _: () = {
 ///
 /// Refer to the [crate level documentation](index.html) for a complete example
 /// of submitting plugins and iterating a plugin registry.
+///
+/// # Keyed registries
+///
+/// A registry whose plugins are looked up by name or some other key can
+/// declare that key as `key = field: Type`, where `&self.field` must coerce to
+/// `&Type`. This implements [`Keyed`] for the plugin type, which enables lookup
+/// through [`inventory::get`][get].
+///
+/// The key type is required because `collect!` only sees the name of the
+/// field, not its declaration. [`#[derive(Collect)]`][derive@Collect] does see
+/// the declaration, and accepts a plain `key = field` when the field is a
+/// reference.
+///
+/// ```
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag, key = name: str);
+///
+/// # inventory::submit!(Flag { short: 'v', name: "verbose" });
+/// #
+/// fn lookup_flag(name: &str) -> Option<&'static Flag> {
+///     inventory::get::<Flag>(name)
+/// }
+/// #
+/// # fn main() {
+/// #     assert_eq!(lookup_flag("verbose").unwrap().short, 'v');
+/// # }
+/// ```
+///
+/// # Unique keys
+///
+/// Declaring the key as `unique_by = field: Type` instead additionally checks
+/// that no two plugins have the same key, which requires the key type to
+/// implement `Debug`. What happens when they do is determined by an optional
+/// `on_duplicate = ...` argument, one of the variants of [`OnDuplicate`]. The
+/// default is to panic the first time the registry is iterated, with a message
+/// listing every duplicated key and where each one was submitted.
+///
+/// ```
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag, unique_by = name: str, on_duplicate = KeepFirst);
+/// ```
+///
+/// With `KeepFirst` or `KeepLast` and the `"alloc"` feature, which plugins are
+/// kept is worked out once after each new registration, in *O*(*n* log *n*)
+/// time, and remembered. Without `"alloc"`, iteration searches the registry
+/// for duplicates of each plugin as it goes, which takes *O*(*n*²) time.
+///
+/// # Named registries
+///
+/// `collect!(Type as NAME)` declares a static [`NamedRegistry`] called `NAME`,
+/// which is separate from the registry of the type itself. Any number of named
+/// registries may hold the same type, and the type does not need a registry of
+/// its own. A visibility may precede the name, as in `collect!(Flag as pub
+/// ADMIN_FLAGS)`.
+///
+/// ```
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag as ADMIN_FLAGS);
+///
+/// inventory::submit!(in ADMIN_FLAGS => Flag { short: 'f', name: "force" });
+/// #
+/// # fn main() {
+/// #     assert_eq!(inventory::iter_in(&ADMIN_FLAGS).count(), 1);
+/// # }
+/// ```
+///
+/// # Generic types
+///
+/// A generic plugin type gets a separate registry for every instantiation of
+/// it. Write the generic parameters as in an impl block, with any bounds that
+/// do not fit there in a `where` clause. This requires the `"alloc"` feature,
+/// as each registry is allocated the first time it is used.
+///
+/// ```
+/// # #[cfg(feature = "alloc")]
+/// # mod example {
+/// use std::marker::PhantomData;
+///
+/// pub trait Event: Sync + 'static {}
+///
+/// pub struct Handler<E> {
+///     name: &'static str,
+///     event: PhantomData<E>,
+/// }
+///
+/// inventory::collect!(impl<E: Event> for Handler<E>);
+///
+/// pub struct Click;
+/// impl Event for Click {}
+///
+/// inventory::submit!(Handler::<Click> {
+///     name: "open link",
+///     event: PhantomData,
+/// });
+///
+/// pub fn click_handlers() -> usize {
+///     inventory::iter::<Handler<Click>>.into_iter().count()
+/// }
+/// # }
+/// #
+/// # fn main() {
+/// #     #[cfg(feature = "alloc")]
+/// #     assert_eq!(example::click_handlers(), 1);
+/// # }
+/// ```
+///
+/// Generic registries are not visited by [`inventory::registries`][registries],
+/// since which instantiations exist is only known once they are used.
+///
+/// # Trait objects
+///
+/// A registry may hold plugins of different types behind a trait object.
+/// Values are entered into it using `submit!(value as dyn Trait)`, or
+/// `submit!(&STATIC as &dyn Trait)` for a value that is already in a static,
+/// and `inventory::iter::<dyn Trait>` yields `&'static dyn Trait`. The trait
+/// must have `Sync` as a supertrait.
+///
+/// ```
+/// pub trait Plugin: Sync {
+///     fn name(&self) -> &'static str;
+/// }
+///
+/// inventory::collect!(dyn Plugin);
+///
+/// struct Gzip;
+///
+/// impl Plugin for Gzip {
+///     fn name(&self) -> &'static str {
+///         "gzip"
+///     }
+/// }
+///
+/// inventory::submit!(Gzip as dyn Plugin);
+///
+/// fn main() {
+///     for plugin in inventory::iter::<dyn Plugin> {
+///         println!("{}", plugin.name());
+///     }
+/// }
+/// ```
+///
+/// # Dynamically loaded libraries
+///
+/// Every executable and shared library that links in the crate declaring a
+/// registry has its own copy of the registry, so plugins submitted from a
+/// library loaded at runtime by `dlopen` normally do not show up in the
+/// executable's registry. Declaring the registry as `collect!(Type, export =
+/// "symbol")` places it in a static with the given symbol name, exported from
+/// every binary that contains it. If the executable exports the symbol
+/// dynamically, for example by being linked with `-rdynamic` on Linux, the
+/// dynamic loader binds a library's references to the registry to the
+/// executable's copy, and the library's plugins join the executable's registry
+/// as the library is loaded. A build script of the executable can request this
+/// by printing `cargo:rustc-link-arg-bins=-rdynamic`.
+///
+/// ```
+/// pub struct Plugin {
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Plugin, export = "myapp_plugins");
+/// ```
+///
+/// The symbol name must be unique within the program. The executable and the
+/// libraries must be built against the same version of `inventory` with the
+/// same Cargo features, because they share the registry's memory layout. To
+/// unload such libraries again, enable the
+/// [`"unload"`][crate#unloading-shared-libraries] feature.
+///
+/// # Foreign code
+///
+/// With the `"ffi"` Cargo feature, `collect!(Type, ffi = "prefix")` exports
+/// functions through which code written in C or other languages can read the
+/// registry, such as when Rust code is linked into a C++ application as a
+/// static library. For `ffi = "handlers"` these are:
+///
+/// - `size_t handlers_count(void)`, the number of plugins;
+/// - `const T *handlers_get(size_t index)`, the plugin at the given position in
+///   the order of [`iter_sorted`], or null if there are not that many. The
+///   plugins are listed once after each new registration, so that indexing
+///   takes constant time;
+/// - `handlers_iter *handlers_iter_new(void)`, which allocates a cursor at the
+///   beginning of the registry;
+/// - `const T *handlers_iter_next(handlers_iter *iter)`, which advances the
+///   cursor and returns the next plugin, or null after the last one;
+/// - `void handlers_iter_free(handlers_iter *iter)`, which releases the cursor.
+///
+/// A panic while reading the registry, such as for a duplicate key under
+/// [`OnDuplicate::Panic`], does not unwind into foreign code: the panic is
+/// caught, and `handlers_count` returns 0 and the other functions return null.
+///
+/// A cursor holds on to the registry the same way an iterator does. The
+/// plugin type should be `#[repr(C)]` if foreign code reads its fields.
+/// [`inventory::c_header`][c_header] produces the matching C declarations.
+///
+/// ```
+/// # #[cfg(feature = "ffi")]
+/// # mod example {
+/// #[repr(C)]
+/// pub struct Handler {
+///     id: u32,
+///     run: extern "C" fn(id: u32),
+/// }
+///
+/// inventory::collect!(Handler, ffi = "handlers");
+/// # }
+/// ```
+///
+/// The exported symbols must be unique within the program. When linking a
+/// static library, the linker only includes the parts of it that are
+/// referenced, which may leave out plugins submitted from other crates unless
+/// the whole archive is linked.
 #[macro_export]
 macro_rules! collect {
+    (dyn $($bound:tt)+) => {
+        impl $crate::Collect for dyn $($bound)+ {
+            const NAME: &'static str = $crate::__private::stringify!(dyn $($bound)+);
+
+            #[inline]
+            fn registry() -> &'static $crate::Registry {
+                static REGISTRY: $crate::Registry = $crate::Registry::new();
+                &REGISTRY
+            }
+
+            #[inline]
+            unsafe fn from_erased(value: *const (), _index: usize) -> &'static Self {
+                unsafe { *value.cast::<&'static Self>() }
+            }
+
+            #[inline]
+            fn erase(value: &&'static Self) -> *const () {
+                (value as *const &'static Self).cast()
+            }
+        }
+
+        $crate::__registry_info!(dyn $($bound)+);
+    };
+
+    (impl<$($param:ident $(: $bound:path)?),* $(,)?> for $ty:ty $(where $($where:tt)+)?) => {
+        impl<$($param $(: $bound)?),*> $crate::Collect for $ty
+        where
+            $ty: 'static,
+            $($($where)+)?
+        {
+            const NAME: &'static str = $crate::__private::stringify!($ty);
+
+            #[inline]
+            fn registry() -> &'static $crate::Registry {
+                static REGISTRIES: $crate::__private::GenericRegistry =
+                    $crate::__private::GenericRegistry::new();
+                REGISTRIES.get::<Self>()
+            }
+
+            $crate::__erasure!($ty);
+        }
+    };
+
+    ($ty:ty as $vis:vis $name:ident) => {
+        $vis static $name: $crate::NamedRegistry<$ty> = $crate::NamedRegistry::new();
+
+        const _: () = {
+            fn registry() -> &'static $crate::Registry {
+                $name.registry()
+            }
+
+            fn len() -> usize {
+                $crate::iter_in(&$name).count()
+            }
+
+            $crate::__registry_info!(value = $crate::__private::Described::<$ty>::named(
+                $crate::__private::stringify!($name),
+                registry,
+                len,
+            ));
+        };
+    };
+
     ($ty:ty) => {
         impl $crate::Collect for $ty {
+            const NAME: &'static str = $crate::__private::stringify!($ty);
+
+            #[inline]
+            fn registry() -> &'static $crate::Registry {
+                static REGISTRY: $crate::Registry = $crate::Registry::new();
+                &REGISTRY
+            }
+
+            $crate::__erasure!($ty);
+        }
+
+        $crate::__registry_info!($ty);
+    };
+
+    ($ty:ty, export = $symbol:literal $(,)?) => {
+        impl $crate::Collect for $ty {
+            const NAME: &'static str = $crate::__private::stringify!($ty);
+
+            #[inline]
+            fn registry() -> &'static $crate::Registry {
+                $crate::__exported_registry!($symbol)
+            }
+
+            $crate::__erasure!($ty);
+        }
+
+        $crate::__registry_info!($ty);
+    };
+
+    ($ty:ty, ffi = $prefix:literal $(,)?) => {
+        $crate::collect!($ty);
+        $crate::__ffi!($ty, $prefix);
+    };
+
+    ($ty:ty, key = $field:tt : $key:ty $(,)?) => {
+        $crate::collect!($ty);
+        $crate::__keyed!($ty, $field, $key);
+    };
+
+    ($ty:ty, key = $field:tt $(,)?) => {
+        $crate::__private::compile_error! {
+            "the type of the key must be given, as in `key = field: Type`"
+        }
+    };
+
+    ($ty:ty, unique_by = $field:tt : $key:ty $(, on_duplicate = $policy:ident)? $(,)?) => {
+        impl $crate::Collect for $ty {
+            const NAME: &'static str = $crate::__private::stringify!($ty);
+
             #[inline]
             fn registry() -> &'static $crate::Registry {
                 static REGISTRY: $crate::Registry = $crate::Registry::new();
                 &REGISTRY
             }
+
+            $crate::__erasure!($ty);
+
+            const UNIQUE: $crate::__private::Option<$crate::__private::Unique<Self>> =
+                $crate::__private::Option::Some($crate::__private::Unique::new(
+                    $crate::__on_duplicate!($($policy)?),
+                ));
+        }
+
+        $crate::__keyed!($ty, $field, $key);
+        $crate::__registry_info!($ty);
+    };
+
+    ($ty:ty, unique_by = $field:tt $(, on_duplicate = $policy:ident)? $(,)?) => {
+        $crate::__private::compile_error! {
+            "the type of the key must be given, as in `unique_by = field: Type`"
         }
     };
 }
@@ -435,148 +1742,953 @@ macro_rules! collect {
 /// invocation is not a statement that needs to be called from `main` in order
 /// to execute.
 ///
-/// # Examples
+/// The submitted value must be a constant expression. Refer to
+/// [`submit_lazy!`] for values that need to be computed at runtime.
 ///
-/// Put `submit!` invocations outside of any function body.
+/// A value followed by `as dyn Trait` is entered into the registry of that
+/// trait object type instead. See [`collect!`][collect#trait-objects].
+///
+/// A list of values in square brackets enters each of the values, all from a
+/// single static node with a single constructor. This is cheaper than a
+/// separate `submit!` for each value when there are many. See also
+/// [`submit_all!`].
 ///
 /// ```
-/// # struct Flag;
-/// #
-/// # impl Flag {
-/// #     const fn new(short: char, name: &'static str) -> Self {
-/// #         Flag
-/// #     }
+/// # struct Flag {
+/// #     short: char,
+/// #     name: &'static str,
 /// # }
 /// #
 /// # inventory::collect!(Flag);
 /// #
-/// inventory::submit! {
-///     Flag::new('v', "verbose")
-/// }
+/// inventory::submit! {[
+///     Flag { short: 'v', name: "verbose" },
+///     Flag { short: 'q', name: "quiet" },
+/// ]}
 /// #
-/// # fn main() {}
+/// # fn main() {
+/// #     assert_eq!(inventory::iter::<Flag>.into_iter().count(), 2);
+/// # }
 /// ```
 ///
-/// Do not try to invoke `submit!` from inside of a function body as it does not
-/// do what you want.
+/// A value preceded by `in NAME =>` is entered into the [`NamedRegistry`]
+/// called `NAME` instead of the registry of its type. See
+/// [`collect!`][collect#named-registries].
 ///
-/// ```compile_fail
-/// // Do not do this.
-/// fn submit_flags(has_verbose_flag: bool) {
-///     if has_verbose_flag {
-///         inventory::submit! {
-///             Flag::new('v', "verbose")
-///         }
-///     }
+/// # Priority
+///
+/// An inner `#![priority(...)]` attribute, whose argument is a constant `i32`
+/// expression, controls the order in which `inventory::iter` visits the plugin
+/// relative to other plugins of the same type. Plugins with higher priority are
+/// visited first. The default priority is 0.
+///
+/// ```
+/// # struct Middleware {
+/// #     name: &'static str,
+/// # }
+/// #
+/// # inventory::collect!(Middleware);
+/// #
+/// inventory::submit! {
+///     #![priority(10)]
+///     Middleware { name: "authenticate" }
 /// }
+///
+/// inventory::submit! {
+///     Middleware { name: "log" }
+/// }
+/// #
+/// # fn main() {
+/// #     let first = inventory::iter::<Middleware>.into_iter().next().unwrap();
+/// #     assert_eq!(first.name, "authenticate");
+/// # }
 /// ```
 ///
-/// Refer to the [crate level documentation](index.html) for a complete example
-/// of instantiating and iterating a plugin registry.
-#[macro_export]
+/// Each plugin is linked into its registry ahead of all plugins of lower
+/// priority, after searching past those of higher priority. A plugin whose
+/// priority is not lower than any registered before it, which includes every
+/// plugin of a registry that does not use priorities, is linked in at the front
+/// in constant time. In the worst case, with many distinct priorities, building
+/// a registry of `n` plugins takes time proportional to `n²`.
+///
+/// # Dependencies
+///
+/// Inner `#![id(...)]`, `#![after(...)]` and `#![before(...)]` attributes
+/// give the submission an identifier, and declare identifiers of other
+/// submissions of the same type that it must be visited after or before by
+/// [`inventory::iter_ordered`][iter_ordered]. They may be combined in one
+/// attribute, as in `#![id(cache), after(db, config)]`. Other iterators do not
+/// take these into account.
+///
+/// ```
+/// # struct Hook {
+/// #     name: &'static str,
+/// # }
+/// #
+/// # inventory::collect!(Hook);
+/// #
+/// inventory::submit! {
+///     #![id(cache), after(db)]
+///     Hook { name: "warm up cache" }
+/// }
+///
+/// inventory::submit! {
+///     #![id(db)]
+///     Hook { name: "connect to database" }
+/// }
+/// ```
+///
+/// # Label
+///
+/// An inner `#![label("...")]` attribute attaches a description to the
+/// submission, which appears in the record written with the `"manifest"`
+/// feature. See [Manifest](index.html#manifest). It has no effect otherwise.
+///
+/// # Examples
+///
+/// Put `submit!` invocations outside of any function body.
+///
+/// ```
+/// # struct Flag;
+/// #
+/// # impl Flag {
+/// #     const fn new(short: char, name: &'static str) -> Self {
+/// #         Flag
+/// #     }
+/// # }
+/// #
+/// # inventory::collect!(Flag);
+/// #
+/// inventory::submit! {
+///     Flag::new('v', "verbose")
+/// }
+/// #
+/// # fn main() {}
+/// ```
+///
+/// Do not try to invoke `submit!` from inside of a function body as it does not
+/// do what you want.
+///
+/// ```compile_fail
+/// // Do not do this.
+/// fn submit_flags(has_verbose_flag: bool) {
+///     if has_verbose_flag {
+///         inventory::submit! {
+///             Flag::new('v', "verbose")
+///         }
+///     }
+/// }
+/// ```
+///
+/// Refer to the [crate level documentation](index.html) for a complete example
+/// of instantiating and iterating a plugin registry.
+#[macro_export]
 macro_rules! submit {
     ($($value:tt)*) => {
         $crate::__do_submit! {
+            used={ #[used] }
+            priority={ 0 }
+            deps=[]
             { $($value)* }
             { $($value)* }
         }
     };
 }
 
+/// Enter every element of a slice into the plugin registry corresponding to
+/// the element type.
+///
+/// The argument is a constant expression of type `&'static [T]`, such as a
+/// reference to a static array generated by a build script. All the elements
+/// are registered from a single static node with a single constructor, and
+/// `inventory::iter::<T>` visits them consecutively in the order of the slice.
+///
+/// # Examples
+///
+/// ```
+/// pub struct Codec {
+///     name: &'static str,
+///     id: u16,
+/// }
+///
+/// inventory::collect!(Codec);
+///
+/// static CODECS: [Codec; 3] = [
+///     Codec { name: "pcm", id: 1 },
+///     Codec { name: "alaw", id: 6 },
+///     Codec { name: "ulaw", id: 7 },
+/// ];
+///
+/// inventory::submit_all!(&CODECS);
+/// #
+/// # fn main() {
+/// #     assert_eq!(inventory::iter::<Codec>.into_iter().count(), 3);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! submit_all {
+    ($values:expr $(,)?) => {
+        $crate::__do_submit! {
+            used={ #[used] }
+            priority={ 0 }
+            deps=[]
+            value={ &$crate::__private::Slice { values: $values } }
+        }
+    };
+}
+
+/// Enter an element into the plugin registry corresponding to its type, with a
+/// value computed the first time the registry is iterated.
+///
+/// Unlike [`submit!`], the expression does not need to be a constant
+/// expression. It is evaluated at most once, on the first occasion that the
+/// element is visited by `inventory::iter::<T>`, after which every iteration
+/// observes the same `&'static T`. The type of the element must be written out
+/// before the expression.
+///
+/// The expression must not itself iterate the registry that the element is
+/// being entered into.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::BTreeMap;
+///
+/// pub struct Codec {
+///     name: &'static str,
+///     aliases: BTreeMap<&'static str, u32>,
+/// }
+///
+/// inventory::collect!(Codec);
+///
+/// inventory::submit_lazy!(Codec, Codec {
+///     name: "deflate",
+///     aliases: BTreeMap::from([("zlib", 1), ("gzip", 2)]),
+/// });
+/// #
+/// # fn main() {
+/// #     let codec = inventory::iter::<Codec>.into_iter().next().unwrap();
+/// #     assert_eq!(codec.aliases["gzip"], 2);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! submit_lazy {
+    ($ty:ty, $init:expr $(,)?) => {
+        #[allow(non_upper_case_globals)]
+        const _: () = {
+            static __LAZY: $crate::__private::Lazy<$ty> = $crate::__private::Lazy::new(|| $init);
+
+            static __INVENTORY: $crate::Node = $crate::Node {
+                value: &__LAZY,
+                next: $crate::__private::AtomicPtr::new($crate::__private::null_mut()),
+                initialized: $crate::__private::AtomicBool::new(false),
+                delivered: $crate::__private::AtomicUsize::new(0),
+                enabled: $crate::__private::AtomicBool::new(true),
+                info: $crate::__submission_info!(),
+                priority: 0,
+                constraints: &$crate::__private::Constraints::NONE,
+            };
+
+            $crate::__link! { __INVENTORY #[used] }
+
+            $crate::__manifest! {
+                used={ #[used] }
+                registry=[]
+                value={ &__LAZY }
+                label=[]
+                {}
+            }
+        };
+    };
+}
+
 // Not public API.
 #[doc(hidden)]
 pub mod __private {
     #[doc(hidden)]
-    pub use core::option::Option;
+    pub use core::{column, compile_error, concat, file, line, module_path, stringify};
+
+    #[doc(hidden)]
+    pub use crate::lazy::Lazy;
+
+    #[doc(hidden)]
+    pub use crate::unique::Unique;
+
+    #[doc(hidden)]
+    pub use crate::named::In;
+
+    #[doc(hidden)]
+    pub use crate::registries::Described;
+
+    #[doc(hidden)]
+    pub use crate::{Constraints, Ref, Slice, Static};
+
+    #[cfg(feature = "alloc")]
+    #[doc(hidden)]
+    pub use crate::generic::GenericRegistry;
+
+    #[cfg(feature = "ffi")]
+    #[doc(hidden)]
+    pub mod ffi {
+        pub use crate::ffi::{count, get, iter_free, iter_new, iter_next};
+    }
+
+    #[cfg(feature = "manifest")]
+    #[doc(hidden)]
+    pub mod manifest {
+        pub use crate::manifest::{len, record, registry};
+    }
+
+    // Collect::from_erased for sized types.
+    #[doc(hidden)]
+    pub unsafe fn from_erased<T>(value: *const (), index: usize) -> &'static T {
+        unsafe { &*value.cast::<T>().add(index) }
+    }
+
+    // Collect::erase for sized types.
+    #[doc(hidden)]
+    pub fn erase<T>(value: &&'static T) -> *const () {
+        (*value as *const T).cast()
+    }
 
     #[cfg(target_family = "wasm")]
     #[doc(hidden)]
     pub use rustversion::attr;
 
+    #[doc(hidden)]
+    pub use core::cmp::{Ord, Ordering};
+
+    #[doc(hidden)]
+    pub use core::option::Option;
+
+    #[doc(hidden)]
+    pub use core::ptr::null_mut;
+
     // Type alias to sidestep clippy::disallowed_types in downstream projects
     // that use Loom.
     #[doc(hidden)]
-    pub type UnsafeCell<T> = core::cell::UnsafeCell<T>;
+    pub type AtomicPtr<T> = core::sync::atomic::AtomicPtr<T>;
 
     // Type alias to sidestep clippy::disallowed_types in downstream projects
     // that use Loom.
-    #[cfg(target_family = "wasm")]
     #[doc(hidden)]
     pub type AtomicBool = core::sync::atomic::AtomicBool;
+
+    // Type alias to sidestep clippy::disallowed_types in downstream projects
+    // that use Loom.
+    #[doc(hidden)]
+    pub type AtomicUsize = core::sync::atomic::AtomicUsize;
 }
 
 // Not public API.
 #[doc(hidden)]
 #[macro_export]
 macro_rules! __do_submit {
-    (used={ $($used:tt)+ } $($value:tt)*) => {
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] { #![used($($attr:tt)+)] $($value:tt)* } { $pound:tt $bang:tt $brackets:tt $($dup:tt)* }) => {
+        $crate::__do_submit! {
+            used={ $pound $brackets }
+            priority={ $($priority)+ }
+            deps=[$($deps)*]
+            { $($value)* }
+            { $($dup)* }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] { #![priority($new:expr)] $($value:tt)* } { $pound:tt $bang:tt $brackets:tt $($dup:tt)* }) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $new }
+            deps=[$($deps)*]
+            { $($value)* }
+            { $($dup)* }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] { #![$($key:ident($($arg:tt)*)),+ $(,)?] $($value:tt)* } { $pound:tt $bang:tt $brackets:tt $($dup:tt)* }) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $($priority)+ }
+            deps=[$($deps)* $($key($($arg)*))+]
+            { $($value)* }
+            { $($dup)* }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] { in $registry:path => $($value:tt)* } { $($dup:tt)* }) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $($priority)+ }
+            deps=[$($deps)*]
+            registry={ $crate::__private::stringify!($registry) }
+            value={ &$crate::__private::In { registry: &$registry, value: &{ $($value)* } } }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] { [$($values:tt)*] } { $($dup:tt)* }) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $($priority)+ }
+            deps=[$($deps)*]
+            value={ &$crate::__private::Slice { values: &[$($values)*] } }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] { $($value:tt)* } { $($dup:tt)* }) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $($priority)+ }
+            deps=[$($deps)*]
+            scan=[]
+            { $($value)* }
+        }
+    };
+
+    // Look for a trailing `as dyn Trait` or `as &dyn Trait` at the top level of
+    // the submitted expression, which submits it into the registry of the trait
+    // object type.
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] scan=[$($expr:tt)+] { as dyn $($bound:tt)+ }) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $($priority)+ }
+            deps=[$($deps)*]
+            value={ &$crate::__private::Ref { value: &{ $($expr)+ } as &'static (dyn $($bound)+) } }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] scan=[$($expr:tt)+] { as & $($lifetime:lifetime)? dyn $($bound:tt)+ }) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $($priority)+ }
+            deps=[$($deps)*]
+            value={ &$crate::__private::Ref { value: $($expr)+ as &'static (dyn $($bound)+) } }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] scan=[$($expr:tt)*] { $first:tt $($rest:tt)* }) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $($priority)+ }
+            deps=[$($deps)*]
+            scan=[$($expr)* $first]
+            { $($rest)* }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $($priority:tt)+ } deps=[$($deps:tt)*] scan=[$($expr:tt)*] {}) => {
+        $crate::__do_submit! {
+            used={ $($used)+ }
+            priority={ $($priority)+ }
+            deps=[$($deps)*]
+            value={ &{ $($expr)* } }
+        }
+    };
+
+    (used={ $($used:tt)+ } priority={ $priority:expr } deps=[$($deps:tt)*] $(registry={ $registry:expr })? value={ $value:expr }) => {
         #[allow(non_upper_case_globals)]
         const _: () = {
             static __INVENTORY: $crate::Node = $crate::Node {
-                value: &{ $($value)* },
-                next: $crate::__private::UnsafeCell::new($crate::__private::Option::None),
-                #[cfg(target_family = "wasm")]
+                value: $value,
+                next: $crate::__private::AtomicPtr::new($crate::__private::null_mut()),
                 initialized: $crate::__private::AtomicBool::new(false),
+                delivered: $crate::__private::AtomicUsize::new(0),
+                enabled: $crate::__private::AtomicBool::new(true),
+                info: $crate::__submission_info!(),
+                priority: $priority,
+                constraints: &$crate::__constraints! {
+                    id=[] after=[] before=[] { $($deps)* }
+                },
             };
 
-            #[cfg_attr(any(target_os = "linux", target_os = "android"), link_section = ".text.startup")]
-            unsafe extern "C" fn __ctor() {
-                unsafe { $crate::ErasedNode::submit(__INVENTORY.value, &__INVENTORY) }
+            $crate::__link! { __INVENTORY $($used)+ }
+
+            $crate::__manifest! {
+                used={ $($used)+ }
+                registry=[$($registry)?]
+                value={ $value }
+                label=[]
+                { $($deps)* }
             }
+        };
+    };
+}
 
-            // Linux/ELF: https://www.exploit-db.com/papers/13234
-            //
-            // macOS: https://blog.timac.org/2016/0716-constructor-and-destructor-attributes/
-            //
-            // Why .CRT$XCU on Windows? https://www.cnblogs.com/sunkang/archive/2011/05/24/2055635.html
-            // 'I'=C init, 'C'=C++ init, 'P'=Pre-terminators and 'T'=Terminators
-            $($used)+
-            #[cfg_attr(
-                all(
-                    not(target_family = "wasm"),
-                    any(
-                        target_os = "linux",
-                        target_os = "android",
-                        target_os = "dragonfly",
-                        target_os = "freebsd",
-                        target_os = "haiku",
-                        target_os = "illumos",
-                        target_os = "netbsd",
-                        target_os = "nto",
-                        target_os = "openbsd",
-                        target_os = "vxworks",
-                        target_os = "none",
-                    )
-                ),
+// Not public API.
+#[cfg(not(feature = "section"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __link {
+    ($node:ident $($used:tt)+) => {
+        $crate::__ctor! { $node $($used)+ }
+    };
+}
+
+// Not public API.
+#[cfg(feature = "section")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __link {
+    ($node:ident $($used:tt)+) => {
+        #[cfg(not(all(
+            not(target_family = "wasm"),
+            any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "haiku",
+                target_os = "illumos",
+                target_os = "netbsd",
+                target_os = "nto",
+                target_os = "openbsd",
+                target_os = "vxworks",
+                target_os = "none",
+            ),
+        )))]
+        $crate::__ctor! { $node $($used)+ }
+
+        // See src/section.rs.
+        #[cfg(all(
+            not(target_family = "wasm"),
+            any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "haiku",
+                target_os = "illumos",
+                target_os = "netbsd",
+                target_os = "nto",
+                target_os = "openbsd",
+                target_os = "vxworks",
+                target_os = "none",
+            ),
+        ))]
+        $($used)+
+        #[link_section = "inventory_0_3"]
+        static __SECTION: &$crate::Node = &$node;
+    };
+}
+
+// Not public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ctor {
+    ($node:ident $($used:tt)+) => {
+        #[cfg_attr(any(target_os = "linux", target_os = "android"), link_section = ".text.startup")]
+        unsafe extern "C" fn __ctor() {
+            $crate::Node::submit(&$node)
+        }
+
+        // Linux/ELF: https://www.exploit-db.com/papers/13234
+        //
+        // macOS: https://blog.timac.org/2016/0716-constructor-and-destructor-attributes/
+        //
+        // Why .CRT$XCU on Windows? https://www.cnblogs.com/sunkang/archive/2011/05/24/2055635.html
+        // 'I'=C init, 'C'=C++ init, 'P'=Pre-terminators and 'T'=Terminators
+        $($used)+
+        #[cfg_attr(
+            all(
+                not(target_family = "wasm"),
+                any(
+                    target_os = "linux",
+                    target_os = "android",
+                    target_os = "dragonfly",
+                    target_os = "freebsd",
+                    target_os = "haiku",
+                    target_os = "illumos",
+                    target_os = "netbsd",
+                    target_os = "nto",
+                    target_os = "openbsd",
+                    target_os = "vxworks",
+                    target_os = "none",
+                )
+            ),
+            link_section = ".init_array",
+        )]
+        #[cfg_attr(
+            target_family = "wasm",
+            $crate::__private::attr(
+                any(all(stable, since(1.85)), since(2024-12-18)),
                 link_section = ".init_array",
-            )]
-            #[cfg_attr(
-                target_family = "wasm",
-                $crate::__private::attr(
-                    any(all(stable, since(1.85)), since(2024-12-18)),
-                    link_section = ".init_array",
-                ),
-            )]
-            #[cfg_attr(
-                any(target_os = "macos", target_os = "ios"),
-                link_section = "__DATA,__mod_init_func,mod_init_funcs",
-            )]
-            #[cfg_attr(windows, link_section = ".CRT$XCU")]
-            static __CTOR: unsafe extern "C" fn() = __ctor;
+            ),
+        )]
+        #[cfg_attr(
+            any(target_os = "macos", target_os = "ios"),
+            link_section = "__DATA,__mod_init_func,mod_init_funcs",
+        )]
+        #[cfg_attr(windows, link_section = ".CRT$XCU")]
+        static __CTOR: unsafe extern "C" fn() = __ctor;
+
+        $crate::__dtor! { $node $($used)+ }
+    };
+}
+
+// Not public API.
+#[cfg(not(feature = "unload"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dtor {
+    ($node:ident $($used:tt)+) => {};
+}
+
+// Not public API.
+#[cfg(feature = "unload")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __dtor {
+    ($node:ident $($used:tt)+) => {
+        // See src/unload.rs.
+        #[cfg(all(
+            not(target_family = "wasm"),
+            any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "haiku",
+                target_os = "illumos",
+                target_os = "netbsd",
+                target_os = "nto",
+                target_os = "openbsd",
+                target_os = "vxworks",
+                target_os = "none",
+            ),
+        ))]
+        #[cfg_attr(any(target_os = "linux", target_os = "android"), link_section = ".text.exit")]
+        unsafe extern "C" fn __dtor() {
+            $crate::Node::remove(&$node)
+        }
+
+        #[cfg(all(
+            not(target_family = "wasm"),
+            any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "haiku",
+                target_os = "illumos",
+                target_os = "netbsd",
+                target_os = "nto",
+                target_os = "openbsd",
+                target_os = "vxworks",
+                target_os = "none",
+            ),
+        ))]
+        $($used)+
+        #[link_section = ".fini_array"]
+        static __DTOR: unsafe extern "C" fn() = __dtor;
+    };
+}
+
+// Not public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __exported_registry {
+    ($symbol:literal) => {{
+        #[export_name = $symbol]
+        static REGISTRY: $crate::Registry = $crate::Registry::new();
+        &REGISTRY
+    }};
+}
+
+// Not public API.
+#[cfg(feature = "ffi")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ffi {
+    ($ty:ty, $prefix:literal) => {
+        const _: () = {
+            #[export_name = $crate::__private::concat!($prefix, "_count")]
+            extern "C" fn count() -> usize {
+                $crate::__private::ffi::count::<$ty>()
+            }
+
+            #[export_name = $crate::__private::concat!($prefix, "_get")]
+            extern "C" fn get(index: usize) -> *const $ty {
+                $crate::__private::ffi::get::<$ty>(index)
+            }
+
+            #[export_name = $crate::__private::concat!($prefix, "_iter_new")]
+            extern "C" fn iter_new() -> *mut $crate::IterSorted<$ty> {
+                $crate::__private::ffi::iter_new::<$ty>()
+            }
+
+            #[export_name = $crate::__private::concat!($prefix, "_iter_next")]
+            unsafe extern "C" fn iter_next(iter: *mut $crate::IterSorted<$ty>) -> *const $ty {
+                unsafe { $crate::__private::ffi::iter_next::<$ty>(iter) }
+            }
+
+            #[export_name = $crate::__private::concat!($prefix, "_iter_free")]
+            unsafe extern "C" fn iter_free(iter: *mut $crate::IterSorted<$ty>) {
+                unsafe { $crate::__private::ffi::iter_free::<$ty>(iter) }
+            }
         };
     };
+}
 
-    ({ #![used($($used:tt)+)] $($value:tt)* } { $pound:tt $bang:tt $brackets:tt $($dup:tt)* }) => {
-        $crate::__do_submit! {
-            used={ $pound $brackets }
-            $($value)*
+// Not public API.
+#[cfg(not(feature = "ffi"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ffi {
+    ($ty:ty, $prefix:literal) => {
+        $crate::__private::compile_error! {
+            "exporting a registry to foreign code requires the \"ffi\" feature of inventory"
         }
     };
+}
 
-    ({ $($value:tt)* } { $($dup:tt)* }) => {
-        $crate::__do_submit! {
-            used={ #[used] }
-            $($value)*
+// Not public API.
+#[cfg(feature = "manifest")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __manifest {
+    (used={ $($used:tt)+ } registry=[$($registry:tt)*] value={ $value:expr } label=[] { label($label:literal) $($rest:tt)* }) => {
+        $crate::__manifest! {
+            used={ $($used)+ }
+            registry=[$($registry)*]
+            value={ $value }
+            label=[$label]
+            { $($rest)* }
+        }
+    };
+
+    (used={ $($used:tt)+ } registry=[$($registry:tt)*] value={ $value:expr } label=[$old:literal] { label $($rest:tt)* }) => {
+        $crate::__private::compile_error! {
+            "a submission can have only one label"
+        }
+    };
+
+    (used={ $($used:tt)+ } registry=[$($registry:tt)*] value={ $value:expr } label=[$($label:tt)*] { $key:ident $args:tt $($rest:tt)* }) => {
+        $crate::__manifest! {
+            used={ $($used)+ }
+            registry=[$($registry)*]
+            value={ $value }
+            label=[$($label)*]
+            { $($rest)* }
+        }
+    };
+
+    (used={ $($used:tt)+ } registry=[] value={ $value:expr } label=[$($label:tt)*] {}) => {
+        $crate::__manifest! {
+            used={ $($used)+ }
+            // Never called. See src/manifest.rs.
+            registry=[$crate::__private::manifest::registry(|| {
+                loop {}
+                $value
+            })]
+            label=[$($label)*]
         }
     };
+
+    (used={ $($used:tt)+ } registry=[$registry:expr] value={ $value:expr } label=[$($label:tt)*] {}) => {
+        $crate::__manifest! {
+            used={ $($used)+ }
+            registry=[$registry]
+            label=[$($label)*]
+        }
+    };
+
+    (used={ $($used:tt)+ } registry=[$registry:expr] label=[$($label:literal)?]) => {
+        // See src/manifest.rs.
+        #[cfg(all(
+            not(target_family = "wasm"),
+            any(
+                target_os = "linux",
+                target_os = "android",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "haiku",
+                target_os = "illumos",
+                target_os = "netbsd",
+                target_os = "nto",
+                target_os = "openbsd",
+                target_os = "vxworks",
+                target_os = "none",
+            ),
+        ))]
+        const _: () = {
+            #[allow(unreachable_code)]
+            const REGISTRY: &str = $registry;
+
+            const LABEL: &str = $crate::__private::concat!("" $(, $label)?);
+
+            const LEN: usize = $crate::__private::manifest::len(
+                REGISTRY,
+                $crate::__private::module_path!(),
+                $crate::__private::file!(),
+                LABEL,
+            );
+
+            $($used)+
+            #[link_section = "inventory_manifest"]
+            static __MANIFEST: [u8; LEN] = $crate::__private::manifest::record(
+                REGISTRY,
+                $crate::__private::module_path!(),
+                $crate::__private::file!(),
+                $crate::__private::line!(),
+                LABEL,
+            );
+        };
+    };
+}
+
+// Not public API.
+#[cfg(not(feature = "manifest"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __manifest {
+    ($($tt:tt)*) => {};
+}
+
+// Not public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __constraints {
+    (id=[$($id:ident)?] after=[$($after:ident)*] before=[$($before:ident)*] {}) => {
+        $crate::__private::Constraints {
+            id: $crate::__private::concat!("" $(, $crate::__private::stringify!($id))?),
+            after: &[$($crate::__private::stringify!($after)),*],
+            before: &[$($crate::__private::stringify!($before)),*],
+        }
+    };
+
+    (id=[] after=[$($after:tt)*] before=[$($before:tt)*] { id($id:ident) $($rest:tt)* }) => {
+        $crate::__constraints! {
+            id=[$id] after=[$($after)*] before=[$($before)*] { $($rest)* }
+        }
+    };
+
+    (id=[$($id:tt)*] after=[$($after:tt)*] before=[$($before:tt)*] { after($($new:ident),* $(,)?) $($rest:tt)* }) => {
+        $crate::__constraints! {
+            id=[$($id)*] after=[$($after)* $($new)*] before=[$($before)*] { $($rest)* }
+        }
+    };
+
+    (id=[$($id:tt)*] after=[$($after:tt)*] before=[$($before:tt)*] { before($($new:ident),* $(,)?) $($rest:tt)* }) => {
+        $crate::__constraints! {
+            id=[$($id)*] after=[$($after)*] before=[$($before)* $($new)*] { $($rest)* }
+        }
+    };
+
+    (id=[$($id:tt)*] after=[$($after:tt)*] before=[$($before:tt)*] { label($label:literal) $($rest:tt)* }) => {
+        $crate::__constraints! {
+            id=[$($id)*] after=[$($after)*] before=[$($before)*] { $($rest)* }
+        }
+    };
+
+    (id=[$old:ident] after=[$($after:tt)*] before=[$($before:tt)*] { id $($rest:tt)* }) => {
+        $crate::__private::compile_error! {
+            "a submission can have only one id"
+        }
+    };
+
+    (id=[$($id:tt)*] after=[$($after:tt)*] before=[$($before:tt)*] { $key:ident $($rest:tt)* }) => {
+        $crate::__private::compile_error! {
+            $crate::__private::concat!(
+                "unsupported attribute `", $crate::__private::stringify!($key), "`; ",
+                "expected `id(...)`, `after(...)`, `before(...)` or `label(...)`, ",
+                "or `priority(...)` or `used(...)` by itself",
+            )
+        }
+    };
+}
+
+// Not public API.
+//
+// Enters a description of the registry declared by collect! into the registry
+// listed by inventory::registries. This is bookkeeping rather than a plugin, so
+// unlike in submit! the node has no manifest record, and a different name than
+// __INVENTORY, by which tools that look for submissions in the symbol table
+// recognize them.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __registry_info {
+    ($ty:ty) => {
+        $crate::__registry_info!(value = $crate::__private::Described::<$ty>::of());
+    };
+
+    (value = $value:expr) => {
+        #[allow(non_upper_case_globals)]
+        const _: () = {
+            static __INVENTORY_REGISTRY: $crate::Node = $crate::Node {
+                value: &$value,
+                next: $crate::__private::AtomicPtr::new($crate::__private::null_mut()),
+                initialized: $crate::__private::AtomicBool::new(false),
+                delivered: $crate::__private::AtomicUsize::new(0),
+                enabled: $crate::__private::AtomicBool::new(true),
+                info: $crate::__submission_info!(),
+                priority: 0,
+                constraints: &$crate::__private::Constraints::NONE,
+            };
+
+            $crate::__link! { __INVENTORY_REGISTRY #[used] }
+        };
+    };
+}
+
+// Not public API. Collect::from_erased and Collect::erase for sized types.
+//
+// The Sized bound lives on a function local to the expansion, not on a helper
+// in inventory, so that collect! of an unsized type is reported at the
+// caller's collect! invocation.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __erasure {
+    ($ty:ty) => {
+        #[inline]
+        unsafe fn from_erased(value: *const (), index: usize) -> &'static Self {
+            unsafe fn plugin<T: Sized>(value: *const (), index: usize) -> &'static T {
+                unsafe { &*value.cast::<T>().add(index) }
+            }
+            unsafe { plugin::<$ty>(value, index) }
+        }
+
+        #[inline]
+        fn erase(value: &&'static Self) -> *const () {
+            (*value as *const Self).cast()
+        }
+    };
+}
+
+// Not public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __keyed {
+    ($ty:ty, $field:tt, $key:ty) => {
+        impl $crate::Keyed for $ty {
+            type Key = $key;
+
+            fn key(&self) -> &Self::Key {
+                &self.$field
+            }
+        }
+    };
+}
+
+// Not public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __on_duplicate {
+    () => {
+        $crate::OnDuplicate::Panic
+    };
+    ($policy:ident) => {
+        $crate::OnDuplicate::$policy
+    };
+}
+
+// Not public API.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __submission_info {
+    () => {
+        $crate::SubmissionInfo::new(
+            $crate::__private::module_path!(),
+            $crate::__private::file!(),
+            $crate::__private::line!(),
+            $crate::__private::column!(),
+        )
+    };
 }
@@ -0,0 +1,204 @@
+// With the "manifest" feature, on ELF targets, every submit! additionally
+// places a record describing the submission in a link section named
+// "inventory_manifest". Nothing at runtime reads it; it exists so that tools
+// can list the registrations contained in a binary without running it. The
+// format of a record is documented in the crate-level docs, and the
+// inventory-dump crate in this repository is a reader for it.
+//
+// Records consist of bytes only, with alignment 1, so that the linker lays
+// them out back to back and the section contents on disk do not depend on
+// relocations being applied.
+
+#[cfg(all(
+    not(target_family = "wasm"),
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "haiku",
+        target_os = "illumos",
+        target_os = "netbsd",
+        target_os = "nto",
+        target_os = "openbsd",
+        target_os = "vxworks",
+        target_os = "none",
+    ),
+))]
+mod elf {
+    use core::ptr;
+
+    extern "Rust" {
+        #[link_name = "__start_inventory_manifest"]
+        static START: [u8; 0];
+    }
+
+    // Guarantees that the section exists, and thus that
+    // __start_inventory_manifest is defined, in a program that contains no
+    // submissions.
+    #[used]
+    #[link_section = "inventory_manifest"]
+    static EMPTY: [u8; 0] = [];
+
+    pub(crate) fn retain() {
+        // A linker that garbage collects sections keeps every input section
+        // named inventory_manifest if the bounds of the section are referenced
+        // from a section that it keeps, which is the case here as this
+        // function is called by every submission's constructor or during the
+        // walk of the "section" feature's link section.
+        unsafe {
+            let _ = ptr::read_volatile(&ptr::addr_of!(EMPTY));
+            let _ = ptr::read_volatile(&ptr::addr_of!(START));
+        }
+    }
+}
+
+#[cfg(not(all(
+    not(target_family = "wasm"),
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "haiku",
+        target_os = "illumos",
+        target_os = "netbsd",
+        target_os = "nto",
+        target_os = "openbsd",
+        target_os = "vxworks",
+        target_os = "none",
+    ),
+)))]
+mod elf {
+    pub(crate) fn retain() {}
+}
+
+pub(crate) use self::elf::retain;
+
+use crate::lazy::Lazy;
+use crate::named::In;
+use crate::{Collect, Ref, Slice, Static};
+
+// Not public API. The type name recorded for the registry that a node with
+// a value of type Self is entered into.
+#[doc(hidden)]
+pub trait RegistryName {
+    const NAME: &'static str;
+}
+
+impl<T: Collect> RegistryName for T {
+    const NAME: &'static str = T::NAME;
+}
+
+impl<T: Collect> RegistryName for Static<T> {
+    const NAME: &'static str = T::NAME;
+}
+
+impl<T: Collect> RegistryName for Slice<T> {
+    const NAME: &'static str = T::NAME;
+}
+
+impl<T: ?Sized + Collect> RegistryName for Ref<T> {
+    const NAME: &'static str = T::NAME;
+}
+
+impl<T: Collect> RegistryName for Lazy<T> {
+    const NAME: &'static str = T::NAME;
+}
+
+// Submissions into a NamedRegistry record the path by which the registry was
+// named in submit!, and never use this impl.
+impl<T> RegistryName for In<T> {
+    const NAME: &'static str = "";
+}
+
+// Not public API. The registry name for the value returned by the given
+// function, which is never called. Generated code passes a closure whose body
+// diverges before evaluating the submitted expression, so that the type of the
+// expression is known without evaluating it a second time at compile time.
+#[doc(hidden)]
+pub const fn registry<N: ?Sized + RegistryName>(_value: fn() -> &'static N) -> &'static str {
+    N::NAME
+}
+
+const VERSION: u8 = 1;
+const HEADER: usize = 9;
+
+// Not public API. The size of the record for a submission with the given
+// strings.
+#[doc(hidden)]
+pub const fn len(registry: &str, module_path: &str, file: &str, label: &str) -> usize {
+    let crate_name = crate_name(module_path);
+    HEADER + 2 * 5 + registry.len() + crate_name + module_path.len() + file.len() + label.len()
+}
+
+// Not public API. The record for a submission, whose size N has been computed
+// by len from the same strings.
+#[doc(hidden)]
+#[allow(clippy::cast_possible_truncation)]
+pub const fn record<const N: usize>(
+    registry: &str,
+    module_path: &str,
+    file: &str,
+    line: u32,
+    label: &str,
+) -> [u8; N] {
+    let mut record = [0; N];
+    record[0] = VERSION;
+    let mut pos = 1;
+    (record, pos) = put_u32(record, pos, N as u32);
+    (record, pos) = put_u32(record, pos, line);
+    (record, pos) = put_str(record, pos, registry.as_bytes(), registry.len());
+    (record, pos) = put_str(record, pos, module_path.as_bytes(), crate_name(module_path));
+    (record, pos) = put_str(record, pos, module_path.as_bytes(), module_path.len());
+    (record, pos) = put_str(record, pos, file.as_bytes(), file.len());
+    (record, pos) = put_str(record, pos, label.as_bytes(), label.len());
+    assert!(pos == N, "inventory manifest record has the wrong size");
+    record
+}
+
+// Length of the first component of a module path, which is the crate name.
+const fn crate_name(module_path: &str) -> usize {
+    let bytes = module_path.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() && bytes[i] != b':' {
+        i += 1;
+    }
+    i
+}
+
+// The helpers below take and return the record by value because const fn
+// cannot use mutable references on the minimum supported compiler.
+
+const fn put_u32<const N: usize>(mut record: [u8; N], pos: usize, value: u32) -> ([u8; N], usize) {
+    let bytes = value.to_le_bytes();
+    let mut i = 0;
+    while i < 4 {
+        record[pos + i] = bytes[i];
+        i += 1;
+    }
+    (record, pos + 4)
+}
+
+// Writes the first len bytes of string, preceded by len as a u16.
+#[allow(clippy::cast_possible_truncation)]
+const fn put_str<const N: usize>(
+    mut record: [u8; N],
+    pos: usize,
+    string: &[u8],
+    len: usize,
+) -> ([u8; N], usize) {
+    assert!(
+        len <= u16::MAX as usize,
+        "string is too long for the inventory manifest",
+    );
+    let prefix = (len as u16).to_le_bytes();
+    record[pos] = prefix[0];
+    record[pos + 1] = prefix[1];
+    let mut i = 0;
+    while i < len {
+        record[pos + 2 + i] = string[i];
+        i += 1;
+    }
+    (record, pos + 2 + len)
+}
@@ -0,0 +1,120 @@
+use crate::{Cursor, ErasedNode, Guard, Node, Registry};
+use core::marker::PhantomData;
+
+/// A plugin registry of its own for elements of type `T`, separate from the
+/// registry associated with the type `T` itself.
+///
+/// Usually declared as `inventory::collect!(T as NAME)`. Elements are entered
+/// into it using `inventory::submit!(in NAME => value)` and iterated using
+/// [`inventory::iter_in(&NAME)`][iter_in].
+///
+/// # Examples
+///
+/// ```
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag as pub ADMIN_FLAGS);
+/// inventory::collect!(Flag as pub USER_FLAGS);
+///
+/// inventory::submit!(in ADMIN_FLAGS => Flag { short: 'f', name: "force" });
+/// inventory::submit!(in USER_FLAGS => Flag { short: 'v', name: "verbose" });
+///
+/// fn main() {
+///     for flag in inventory::iter_in(&ADMIN_FLAGS) {
+///         println!("-{}, --{}", flag.short, flag.name);
+///     }
+/// #
+/// #     assert_eq!(inventory::iter_in(&USER_FLAGS).count(), 1);
+/// }
+/// ```
+pub struct NamedRegistry<T: 'static> {
+    registry: Registry,
+    marker: PhantomData<&'static T>,
+}
+
+impl<T> NamedRegistry<T> {
+    /// Create an empty registry, to be placed in a static.
+    pub const fn new() -> Self {
+        NamedRegistry {
+            registry: Registry::new(),
+            marker: PhantomData,
+        }
+    }
+
+    // Not public API. Used by generated code.
+    #[doc(hidden)]
+    pub fn registry(&'static self) -> &'static Registry {
+        &self.registry
+    }
+}
+
+// Not public API. Used by generated code.
+//
+// The node submitted by `submit!(in NAME => value)`.
+#[doc(hidden)]
+pub struct In<T: 'static> {
+    pub registry: &'static NamedRegistry<T>,
+    pub value: &'static T,
+}
+
+impl<T: Sync> ErasedNode for In<T> {
+    fn registry(&self) -> &'static Registry {
+        &self.registry.registry
+    }
+
+    fn value(&self) -> *const () {
+        (self.value as *const T).cast()
+    }
+}
+
+/// Iterate over the plugins in a [`NamedRegistry`].
+///
+/// Plugins are visited in the same order as by
+/// [`inventory::iter`][crate::iter].
+pub fn iter_in<T>(registry: &'static NamedRegistry<T>) -> IterIn<T> {
+    let guard = Guard::new(&registry.registry);
+    IterIn {
+        guard,
+        registry,
+        cursor: Cursor::Start,
+    }
+}
+
+/// Iterator returned by [`iter_in`].
+pub struct IterIn<T: 'static> {
+    guard: Guard,
+    registry: &'static NamedRegistry<T>,
+    cursor: Cursor<&'static Node>,
+}
+
+impl<T> Iterator for IterIn<T> {
+    type Item = &'static T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = match self.cursor {
+            Cursor::Start => self.registry.registry.head(),
+            Cursor::At(node) => node.next_node(),
+            Cursor::End => None,
+        };
+        let Some(node) = node else {
+            self.cursor = Cursor::End;
+            return None;
+        };
+        self.cursor = Cursor::At(node);
+        // Every node in a NamedRegistry<T> was submitted as an In<T>.
+        Some(unsafe { &*node.value.value().cast::<T>() })
+    }
+}
+
+impl<T> Clone for IterIn<T> {
+    fn clone(&self) -> Self {
+        IterIn {
+            guard: self.guard.clone(),
+            registry: self.registry,
+            cursor: self.cursor,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use core::hint;
+use core::mem;
+use core::sync::atomic::{AtomicU8, Ordering};
+
+const UNINIT: u8 = 0;
+const RUNNING: u8 = 1;
+const DONE: u8 = 2;
+
+// Minimal spinning equivalent of std::sync::Once, usable from no_std.
+pub(crate) struct Once {
+    state: AtomicU8,
+}
+
+impl Once {
+    pub(crate) const fn new() -> Self {
+        Once {
+            state: AtomicU8::new(UNINIT),
+        }
+    }
+
+    pub(crate) fn call_once(&self, f: impl FnOnce()) {
+        if self.state.load(Ordering::Acquire) == DONE {
+            return;
+        }
+
+        loop {
+            match self
+                .state
+                .compare_exchange(UNINIT, RUNNING, Ordering::Acquire, Ordering::Acquire)
+            {
+                Ok(_) => {
+                    // If f panics, let the next caller try again rather than
+                    // leave everyone else spinning forever.
+                    let guard = Reset(&self.state);
+                    f();
+                    mem::forget(guard);
+                    self.state.store(DONE, Ordering::Release);
+                    return;
+                }
+                Err(DONE) => return,
+                Err(_) => hint::spin_loop(),
+            }
+        }
+    }
+}
+
+struct Reset<'a>(&'a AtomicU8);
+
+impl Drop for Reset<'_> {
+    fn drop(&mut self) {
+        self.0.store(UNINIT, Ordering::Release);
+    }
+}
@@ -0,0 +1,295 @@
+use crate::{Collect, Element, Elements, Guard, Node, SubmissionInfo};
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::any;
+use core::cmp::Reverse;
+use core::fmt::{self, Debug, Display};
+use core::marker::PhantomData;
+
+/// Iterate over plugins registered of a given type, in an order that satisfies
+/// the dependencies declared between their submissions.
+///
+/// A submission is given an identifier by an inner `#![id(...)]` attribute,
+/// and is ordered relative to other submissions of the same type by
+/// `#![after(...)]` and `#![before(...)]`, which take a list of identifiers.
+/// Several submissions may share an identifier, in which case a dependency on
+/// it applies to every one of them. Among plugins that are not constrained
+/// relative to each other, the order is the same as that of
+/// [`iter_sorted`][crate::iter_sorted].
+///
+/// # Errors
+///
+/// Returns an error if some submission depends on an identifier that no
+/// submission of this type has, or if the dependencies form a cycle, which
+/// includes a submission that depends on its own identifier. The error
+/// displays where each of the submissions involved is located.
+///
+/// # Examples
+///
+/// ```
+/// pub struct Hook {
+///     name: &'static str,
+///     run: fn(),
+/// }
+///
+/// inventory::collect!(Hook);
+///
+/// inventory::submit! {
+///     #![id(cache), after(db)]
+///     Hook { name: "warm up cache", run: || {} }
+/// }
+///
+/// inventory::submit! {
+///     #![id(db)]
+///     Hook { name: "connect to database", run: || {} }
+/// }
+///
+/// fn main() {
+///     let hooks = inventory::iter_ordered::<Hook>().unwrap();
+///     let names: Vec<&str> = hooks.map(|hook| hook.name).collect();
+///     assert_eq!(names, ["connect to database", "warm up cache"]);
+/// }
+/// ```
+pub fn iter_ordered<T: ?Sized + Collect>() -> Result<IterOrdered<T>, OrderError<T>> {
+    let elements = Elements::<T>::sorted();
+    let guard = elements.guard.clone();
+    let head = elements.head;
+    let elements: Vec<Element> = elements.collect();
+
+    // Identifiers of the submissions in the registry, including ones that
+    // iteration leaves out, which have no position among the elements. Each
+    // element with an identifier is listed with its position, to look up the
+    // ones a dependency refers to.
+    let mut ids: Vec<(&str, Option<usize>)> = elements
+        .iter()
+        .enumerate()
+        .filter(|(_, element)| !element.node.constraints.id.is_empty())
+        .map(|(i, element)| (element.node.constraints.id, Some(i)))
+        .collect();
+    let mut node = head;
+    while let Some(current) = node {
+        if !current.constraints.id.is_empty() {
+            ids.push((current.constraints.id, None));
+        }
+        node = current.next_node();
+    }
+    ids.sort_unstable();
+    let with_id = |id: &'static str| {
+        let start = ids.partition_point(|&(other, _)| other < id);
+        ids[start..]
+            .iter()
+            .take_while(move |&&(other, _)| other == id)
+            .filter_map(|&(_, i)| i)
+    };
+
+    // An edge from each element to every element that must come after it. A
+    // submission that depends on its own identifier depends on itself, which
+    // is reported as a cycle.
+    let mut successors = vec![Vec::new(); elements.len()];
+    let mut predecessors = vec![Vec::new(); elements.len()];
+    for (i, element) in elements.iter().enumerate() {
+        let constraints = element.node.constraints;
+        for &after in constraints.after {
+            for j in with_id(after) {
+                successors[j].push(i);
+                predecessors[i].push(j);
+            }
+        }
+        for &before in constraints.before {
+            for j in with_id(before) {
+                successors[i].push(j);
+                predecessors[j].push(i);
+            }
+        }
+    }
+
+    // Repeatedly visit the earliest element, in iteration order, all of whose
+    // predecessors have been visited.
+    let mut remaining: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+    let mut ready: BinaryHeap<Reverse<usize>> = (0..elements.len())
+        .filter(|&i| remaining[i] == 0)
+        .map(Reverse)
+        .collect();
+    let mut order = Vec::with_capacity(elements.len());
+    while let Some(Reverse(i)) = ready.pop() {
+        order.push(elements[i]);
+        for &j in &successors[i] {
+            remaining[j] -= 1;
+            if remaining[j] == 0 {
+                ready.push(Reverse(j));
+            }
+        }
+    }
+
+    let missing = missing(head, &ids);
+    let cycle = if order.len() < elements.len() {
+        cycle(&elements, &predecessors, &remaining)
+    } else {
+        Vec::new()
+    };
+    if missing.is_empty() && cycle.is_empty() {
+        Ok(IterOrdered {
+            guard,
+            elements: order.into_iter(),
+            marker: PhantomData,
+        })
+    } else {
+        Err(OrderError {
+            _guard: guard,
+            missing,
+            cycle,
+            marker: PhantomData,
+        })
+    }
+}
+
+// Every dependency of a submission in the registry on an identifier that no
+// submission in the registry has, including submissions that iteration leaves
+// out.
+fn missing(
+    head: Option<&'static Node>,
+    ids: &[(&str, Option<usize>)],
+) -> Vec<(&'static Node, &'static str)> {
+    let has_id = |id: &str| ids.binary_search_by(|&(other, _)| other.cmp(id)).is_ok();
+    let mut missing = Vec::new();
+    let mut node = head;
+    while let Some(current) = node {
+        let constraints = current.constraints;
+        for &id in constraints.after.iter().chain(constraints.before) {
+            if !has_id(id) {
+                missing.push((current, id));
+            }
+        }
+        node = current.next_node();
+    }
+    missing
+}
+
+// A cycle among the elements that could not be ordered. Each of them has a
+// predecessor that could not be ordered either, so following predecessors from
+// any one of them eventually comes back around.
+fn cycle(elements: &[Element], predecessors: &[Vec<usize>], remaining: &[usize]) -> Vec<Element> {
+    let mut path = Vec::new();
+    let mut i = remaining.iter().position(|&n| n > 0).unwrap();
+    loop {
+        if let Some(start) = path.iter().position(|&j| j == i) {
+            // Reverse into the direction of the dependencies, starting from the
+            // element that comes first in iteration order.
+            let mut cycle = path.split_off(start);
+            cycle.reverse();
+            let first = (0..cycle.len()).min_by_key(|&k| cycle[k]).unwrap();
+            cycle.rotate_left(first);
+            return cycle.into_iter().map(|j| elements[j]).collect();
+        }
+        path.push(i);
+        i = *predecessors[i].iter().find(|&&j| remaining[j] > 0).unwrap();
+    }
+}
+
+/// Iterator returned by [`iter_ordered`].
+pub struct IterOrdered<T: ?Sized + 'static> {
+    guard: Guard,
+    elements: vec::IntoIter<Element>,
+    marker: PhantomData<T>,
+}
+
+impl<T: ?Sized + Collect> Iterator for IterOrdered<T> {
+    type Item = &'static T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        Some(unsafe { element.get::<T>() })
+    }
+}
+
+impl<T: ?Sized> Clone for IterOrdered<T> {
+    fn clone(&self) -> Self {
+        IterOrdered {
+            guard: self.guard.clone(),
+            elements: self.elements.clone(),
+            marker: PhantomData,
+        }
+    }
+}
+
+/// Error returned by [`iter_ordered`] when the declared dependencies cannot be
+/// satisfied.
+///
+/// Displays as a list of every dependency on a missing identifier and of the
+/// submissions that form a cycle, with the locations where they were
+/// submitted.
+pub struct OrderError<T: ?Sized + 'static> {
+    _guard: Guard,
+    missing: Vec<(&'static Node, &'static str)>,
+    cycle: Vec<Element>,
+    marker: PhantomData<T>,
+}
+
+impl<T: ?Sized + Collect> OrderError<T> {
+    /// Every dependency on an identifier that no submission has, as the
+    /// location of the submission that declares the dependency and the missing
+    /// identifier.
+    pub fn missing(&self) -> impl Iterator<Item = (&'static SubmissionInfo, &'static str)> + '_ {
+        self.missing.iter().map(|&(node, id)| (&node.info, id))
+    }
+
+    /// Plugins whose dependencies form a cycle, each of which must come before
+    /// the next and the last before the first. Empty if there is no cycle.
+    pub fn cyclic(&self) -> impl Iterator<Item = (&'static T, &'static SubmissionInfo)> + '_ {
+        self.cycle
+            .iter()
+            .map(|&element| (unsafe { element.get::<T>() }, &element.node.info))
+    }
+}
+
+impl<T: ?Sized + Collect> Display for OrderError<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "cannot order plugins of type `{}`:",
+            any::type_name::<T>(),
+        )?;
+        for &(node, id) in &self.missing {
+            let relation = if node.constraints.after.contains(&id) {
+                "after"
+            } else {
+                "before"
+            };
+            write!(
+                formatter,
+                "\n    {} must come {} `{}`, which is not the id of any submission",
+                Site(node),
+                relation,
+                id,
+            )?;
+        }
+        if let Some(first) = self.cycle.first() {
+            formatter.write_str("\n    cycle: ")?;
+            for element in &self.cycle {
+                write!(formatter, "{}, before ", Site(element.node))?;
+            }
+            write!(formatter, "{}", Site(first.node))?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: ?Sized + Collect> Debug for OrderError<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self, formatter)
+    }
+}
+
+struct Site(&'static Node);
+
+impl Display for Site {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let id = self.0.constraints.id;
+        if id.is_empty() {
+            write!(formatter, "submission at {}", self.0.info)
+        } else {
+            write!(formatter, "`{}` at {}", id, self.0.info)
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use crate::{
+    iter_sorted, Collect, Cursor, Elements, ErasedNode, Guard, Node, Registry, SubmissionInfo,
+};
+use core::any::{self, TypeId};
+use core::fmt::{self, Debug};
+use core::marker::PhantomData;
+use core::ptr;
+use core::sync::atomic::Ordering;
+
+/// Iterate over the plugin registries declared in the program by
+/// [`collect!`][crate::collect].
+///
+/// Every `collect!` and `#[derive(Collect)]` enters a [`RegistryInfo`]
+/// describing the registry into this one, whether or not any plugins have
+/// been submitted to it. Registries of generic types, declared by
+/// `collect!(impl<...> for Type<...>)`, are left out because the set of their
+/// instantiations is not known ahead of time. Registries are visited in order
+/// of the source location of their `collect!`.
+///
+/// # Examples
+///
+/// ```
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag);
+///
+/// inventory::submit!(Flag { short: 'v', name: "verbose" });
+///
+/// fn main() {
+///     for registry in inventory::registries() {
+///         println!("{}: {} plugins", registry.type_name(), registry.len());
+///         for info in registry.submissions() {
+///             println!("    submitted at {}", info);
+///         }
+///     }
+/// #
+/// #     let flags = inventory::registries()
+/// #         .find(|registry| registry.is::<Flag>())
+/// #         .unwrap();
+/// #     assert_eq!(flags.len(), 1);
+/// }
+/// ```
+pub fn registries() -> impl Iterator<Item = &'static RegistryInfo> + Clone {
+    iter_sorted::<Entry>().map(|entry| &entry.0)
+}
+
+/// Description of a plugin registry, as visited by [`registries`].
+pub struct RegistryInfo {
+    name: Option<&'static str>,
+    type_name: fn() -> &'static str,
+    type_id: fn() -> TypeId,
+    registry: fn() -> &'static Registry,
+    len: fn() -> usize,
+}
+
+impl RegistryInfo {
+    /// Name of the type of the plugins in this registry.
+    ///
+    /// This is [`core::any::type_name`] of the type, and subject to the same
+    /// caveats.
+    pub fn type_name(&self) -> &'static str {
+        (self.type_name)()
+    }
+
+    /// [`TypeId`] of the type of the plugins in this registry.
+    pub fn type_id(&self) -> TypeId {
+        (self.type_id)()
+    }
+
+    /// Whether the plugins in this registry are of type `T`.
+    pub fn is<T: ?Sized + 'static>(&self) -> bool {
+        self.type_id() == TypeId::of::<T>()
+    }
+
+    /// The name of a [`NamedRegistry`][crate::NamedRegistry], as written in
+    /// `collect!(Type as NAME)`, or `None` for the registry associated with a
+    /// type.
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Number of plugins that iterating the registry visits.
+    ///
+    /// Unlike [`inventory::iter`][crate::iter], counting does not panic if the
+    /// registry contains plugins with duplicate keys.
+    pub fn len(&self) -> usize {
+        (self.len)()
+    }
+
+    /// Whether iterating the registry visits no plugins.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the locations of the `submit!` invocations, and other
+    /// registrations, that have entered plugins into this registry.
+    ///
+    /// A submission of a slice is visited once, however many plugins it
+    /// contributes. Submissions that have been [disabled][crate::disable] are
+    /// left out.
+    pub fn submissions(&self) -> impl Iterator<Item = &'static SubmissionInfo> + Clone {
+        let registry = (self.registry)();
+        Submissions {
+            _guard: Guard::new(registry),
+            registry,
+            cursor: Cursor::Start,
+        }
+    }
+}
+
+fn len<T: ?Sized + Collect>() -> usize {
+    Elements::<T>::unchecked().count()
+}
+
+impl Debug for RegistryInfo {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let mut debug = formatter.debug_struct("RegistryInfo");
+        if let Some(name) = self.name {
+            debug.field("name", &name);
+        }
+        debug
+            .field("type_name", &self.type_name())
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+// The registry into which Described nodes enter a RegistryInfo each. It is
+// iterated as a registry of Entry, a private type with the same layout, rather
+// than through an impl of Collect for RegistryInfo, which the compiler would
+// point out to users whose own type is missing an impl of Collect.
+#[repr(transparent)]
+struct Entry(RegistryInfo);
+
+impl Collect for Entry {
+    const NAME: &'static str = "inventory::RegistryInfo";
+
+    #[inline]
+    fn registry() -> &'static Registry {
+        static REGISTRY: Registry = Registry::new();
+        &REGISTRY
+    }
+
+    #[inline]
+    unsafe fn from_erased(value: *const (), index: usize) -> &'static Self {
+        unsafe { crate::__private::from_erased::<Self>(value, index) }
+    }
+
+    #[inline]
+    fn erase(value: &&'static Self) -> *const () {
+        crate::__private::erase(value)
+    }
+}
+
+// Not public API. Used by generated code.
+//
+// The node through which collect! of T enters a description of the registry of
+// T into the one visited by inventory::registries.
+#[doc(hidden)]
+pub struct Described<T: ?Sized> {
+    info: RegistryInfo,
+    marker: PhantomData<fn(&T)>,
+}
+
+impl<T: ?Sized + Collect> Described<T> {
+    // The registry associated with the type T.
+    pub const fn of() -> Self {
+        Described {
+            info: RegistryInfo {
+                name: None,
+                type_name: any::type_name::<T>,
+                type_id: TypeId::of::<T>,
+                registry: T::registry,
+                len: len::<T>,
+            },
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: 'static> Described<T> {
+    // A NamedRegistry<T>, with functions that return its Registry and the
+    // number of plugins in it.
+    pub const fn named(
+        name: &'static str,
+        registry: fn() -> &'static Registry,
+        len: fn() -> usize,
+    ) -> Self {
+        Described {
+            info: RegistryInfo {
+                name: Some(name),
+                type_name: any::type_name::<T>,
+                type_id: TypeId::of::<T>,
+                registry,
+                len,
+            },
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<T: ?Sized> ErasedNode for Described<T> {
+    fn registry(&self) -> &'static Registry {
+        <Entry as Collect>::registry()
+    }
+
+    fn value(&self) -> *const () {
+        ptr::addr_of!(self.info).cast()
+    }
+}
+
+#[derive(Clone)]
+struct Submissions {
+    _guard: Guard,
+    registry: &'static Registry,
+    cursor: Cursor<&'static Node>,
+}
+
+impl Iterator for Submissions {
+    type Item = &'static SubmissionInfo;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let node = match self.cursor {
+                Cursor::Start => self.registry.head(),
+                Cursor::At(node) => node.next_node(),
+                Cursor::End => None,
+            };
+            let Some(node) = node else {
+                self.cursor = Cursor::End;
+                return None;
+            };
+            self.cursor = Cursor::At(node);
+            if node.enabled.load(Ordering::Relaxed) {
+                return Some(&node.info);
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// With the "section" feature, on ELF targets, `submit!` does not produce a
+// constructor. Instead every submitted Node is referenced from a pointer placed
+// in a link section named "inventory_0_3", and the linker synthesizes the
+// symbols __start_inventory_0_3 and __stop_inventory_0_3 delimiting the
+// contiguous array of all such pointers in the final binary or shared object.
+//
+// The section name carries the semver-major version of this crate, because the
+// layout of Node differs between incompatible versions and two of them may be
+// linked into the same binary. It must be changed together with the version.
+//
+// The first time any registry is accessed, the whole array is walked once and
+// each node is linked into the registry of its type, exactly as the constructor
+// would have done, but without running any code before main. Every later access
+// only pays for the load of INIT's state that finds the walk already done.
+//
+// There is one section for all types rather than one per registry that
+// iteration could walk directly, because no macro is able to name a section per
+// type. A section name has to be a string literal, and submit! does not even
+// know the type of the submitted value, only its expression. Even collect!,
+// which does, could only derive a name from the type as written, which neither
+// tells apart types of the same name in different modules nor yields a valid
+// identifier for generic and trait object types, for which the linker would
+// not synthesize the __start_ and __stop_ symbols. Linking the nodes into the
+// lists of their registries also keeps everything built on those lists, such
+// as runtime registration and priorities, working the same in both modes.
+
+use crate::once::Once;
+use crate::Node;
+use core::mem;
+use core::ptr;
+use core::slice;
+
+extern "Rust" {
+    #[link_name = "__start_inventory_0_3"]
+    static START: [&'static Node; 0];
+    #[link_name = "__stop_inventory_0_3"]
+    static STOP: [&'static Node; 0];
+}
+
+// Guarantees that the section exists, and thus that __start_inventory_0_3 and
+// __stop_inventory_0_3 are defined, even in a program that contains no
+// submissions.
+#[used]
+#[link_section = "inventory_0_3"]
+static EMPTY: [&Node; 0] = [];
+
+static INIT: Once = Once::new();
+
+pub(crate) fn init() {
+    INIT.call_once(|| {
+        for &node in entries() {
+            node.submit();
+        }
+    });
+}
+
+fn entries() -> &'static [&'static Node] {
+    unsafe {
+        // Keep the object containing EMPTY from being discarded by the linker.
+        let _ = ptr::read_volatile(&ptr::addr_of!(EMPTY));
+        let start = ptr::addr_of!(START).cast::<&'static Node>();
+        let stop = ptr::addr_of!(STOP).cast::<&'static Node>();
+        let len = (stop as usize - start as usize) / mem::size_of::<&Node>();
+        slice::from_raw_parts(start, len)
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{Collect, Elements, Node};
+
+/// Iterate over plugins registered of a given type, in a deterministic order.
+///
+/// Like [`inventory::iter`][crate::iter], this visits plugins in order of
+/// decreasing [priority][crate::submit#priority]. Plugins of equal priority,
+/// however, are visited in order of the location of their `submit!` invocation
+/// rather than in whatever order the linker laid them out: by module path, then
+/// file, line, and column. Plugins registered at runtime through
+/// [`register`][fn@crate::register] come before submitted plugins of the same
+/// priority.
+///
+/// With the `"alloc"` feature, the registry is sorted once after each new
+/// registration and the result is remembered. Without it, no memory is
+/// allocated, and instead each step of the iterator scans the whole registry,
+/// so visiting all of *n* plugins takes *O*(*n*²) time.
+///
+/// # Examples
+///
+/// ```
+/// # struct Flag {
+/// #     short: char,
+/// #     name: &'static str,
+/// # }
+/// #
+/// # inventory::collect!(Flag);
+/// #
+/// fn print_help() {
+///     for flag in inventory::iter_sorted::<Flag>() {
+///         println!("-{}, --{}", flag.short, flag.name);
+///     }
+/// }
+/// ```
+pub fn iter_sorted<T: ?Sized + Collect>() -> IterSorted<T> {
+    IterSorted {
+        elements: Elements::sorted(),
+    }
+}
+
+/// Iterator returned by [`iter_sorted`].
+pub struct IterSorted<T: ?Sized + 'static> {
+    elements: Elements<T>,
+}
+
+impl<T: ?Sized + Collect> IterSorted<T> {
+    pub(crate) fn unchecked() -> Self {
+        IterSorted {
+            elements: Elements {
+                sorted: true,
+                ..Elements::unchecked()
+            },
+        }
+    }
+
+    pub(crate) fn head(&self) -> Option<&'static Node> {
+        self.elements.head
+    }
+}
+
+impl<T: ?Sized + Collect> Iterator for IterSorted<T> {
+    type Item = &'static T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let element = self.elements.next()?;
+        Some(unsafe { element.get::<T>() })
+    }
+}
+
+impl<T: ?Sized> Clone for IterSorted<T> {
+    fn clone(&self) -> Self {
+        IterSorted {
+            elements: self.elements.clone(),
+        }
+    }
+}
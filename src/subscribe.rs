@@ -0,0 +1,225 @@
+use crate::{Collect, Element, Guard, Node, Registry};
+use alloc::boxed::Box;
+use core::mem;
+use core::ptr;
+use core::sync::atomic::{self, Ordering};
+#[cfg(feature = "unload")]
+use core::sync::atomic::{AtomicBool, AtomicPtr};
+
+// One subscription to a registry. The subscribers of a registry form a list
+// that is only ever prepended to, so the subscriber at the head of the list has
+// the highest index and the list's length is one more than that index.
+//
+// The callback is a `fn(&'static T)`, called by `call` for each value of a
+// node. With the "unload" feature, a subscription made from a shared library is
+// disabled when the library is unloaded, as its callback then no longer exists.
+// Its Subscriber stays in the list, which other threads may be walking, and
+// keeps its index, as every node counts its deliveries by index.
+pub(crate) struct Subscriber {
+    index: usize,
+    callback: *const (),
+    call: unsafe fn(*const (), &'static Node),
+    next: *mut Subscriber,
+    #[cfg(feature = "unload")]
+    removed: AtomicBool,
+    #[cfg(feature = "unload")]
+    registry: &'static Registry,
+    #[cfg(feature = "unload")]
+    own_next: *mut Subscriber,
+}
+
+/// Call a function for every plugin of type `T`, including ones that are
+/// registered later.
+///
+/// The callback is first called for each plugin already in the registry,
+/// before `subscribe` returns. After that, it is called for every plugin entered into the registry
+/// at runtime, such as by [`register`][fn@crate::register], on the thread that
+/// registers it and just after the plugin has become visible to
+/// [`inventory::iter`][crate::iter].
+///
+/// Every plugin is passed to every callback exactly once, even when a plugin
+/// is registered concurrently with the subscription. Callbacks see all plugins
+/// entered into the registry, including ones that iteration leaves out because
+/// of the [policy for duplicate keys][crate::collect#unique-keys].
+///
+/// A subscription lasts for the rest of the program, or with the
+/// [`"unload"`][crate#unloading-shared-libraries] feature, until the shared
+/// library that made it is unloaded. Unloading waits for callbacks of the
+/// library that are running in other threads to return.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Mutex;
+///
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag);
+///
+/// inventory::submit!(Flag { short: 'v', name: "verbose" });
+///
+/// static SEEN: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+///
+/// fn on_flag(flag: &'static Flag) {
+///     SEEN.lock().unwrap().push(flag.name);
+/// }
+///
+/// fn main() {
+///     inventory::subscribe::<Flag>(on_flag);
+///     assert_eq!(*SEEN.lock().unwrap(), ["verbose"]);
+///
+///     inventory::register_boxed(Box::new(Flag { short: 'q', name: "quiet" }));
+///     assert_eq!(*SEEN.lock().unwrap(), ["verbose", "quiet"]);
+/// }
+/// ```
+pub fn subscribe<T: ?Sized + Collect>(callback: fn(&'static T)) {
+    let registry = T::registry();
+
+    // Link in the nodes that are not submitted by a constructor before there
+    // is any subscriber that could be called in the middle of doing so.
+    crate::init();
+
+    #[cfg(feature = "unload")]
+    crate::unload::watch_exit();
+
+    let new = Box::into_raw(Box::new(Subscriber {
+        index: 0,
+        callback: callback as *const (),
+        call: call::<T>,
+        next: ptr::null_mut(),
+        #[cfg(feature = "unload")]
+        removed: AtomicBool::new(false),
+        #[cfg(feature = "unload")]
+        registry,
+        #[cfg(feature = "unload")]
+        own_next: ptr::null_mut(),
+    }));
+    #[cfg(feature = "unload")]
+    own(new);
+
+    let mut head = registry.subscribers.load(Ordering::Acquire);
+    loop {
+        unsafe {
+            (*new).index = head.as_ref().map_or(0, |head| head.index + 1);
+            (*new).next = head;
+        }
+        match registry.subscribers.compare_exchange_weak(
+            head,
+            new,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => break,
+            Err(current) => head = current,
+        }
+    }
+
+    // Pairs with the fence in notify. Either the submitting thread sees this
+    // subscriber, or the replay below sees the submitted node, or both.
+    atomic::fence(Ordering::SeqCst);
+
+    let _guard = Guard::new(registry);
+    let mut node = registry.linked();
+    while let Some(current) = node {
+        deliver(current, new);
+        node = current.next_node();
+    }
+}
+
+unsafe fn call<T: ?Sized + Collect>(callback: *const (), node: &'static Node) {
+    let callback: fn(&'static T) = unsafe { mem::transmute(callback) };
+    for index in 0..node.value.count() {
+        // Every node linked into the registry of T contributes values of type
+        // T.
+        callback(unsafe { Element { node, index }.get::<T>() });
+    }
+}
+
+// Called by Registry::submit after a node has been linked into the registry.
+pub(crate) fn notify(registry: &Registry, node: &'static Node) {
+    atomic::fence(Ordering::SeqCst);
+    let head = registry.subscribers.load(Ordering::Acquire);
+    if !head.is_null() {
+        deliver(node, head);
+    }
+}
+
+// Call each subscriber from `head` onward that has not yet been called for
+// `node`. The node counts how many subscribers, in order of subscription, it
+// has been delivered to, and each subscriber is called by the thread that
+// advances the count past it. So when a node is linked while a subscription is
+// being replayed, it does not matter which of the two threads gets to it first.
+fn deliver(node: &'static Node, head: *mut Subscriber) {
+    let count = unsafe { head.as_ref() }.map_or(0, |head| head.index + 1);
+    let mut delivered = node.delivered.load(Ordering::Acquire);
+    while delivered < count {
+        match node.delivered.compare_exchange_weak(
+            delivered,
+            delivered + 1,
+            Ordering::AcqRel,
+            Ordering::Acquire,
+        ) {
+            Ok(_) => {
+                find(head, delivered).notify(node);
+                delivered += 1;
+            }
+            Err(current) => delivered = current,
+        }
+    }
+}
+
+fn find(mut subscriber: *mut Subscriber, index: usize) -> &'static Subscriber {
+    loop {
+        // Subscribers are never freed once they are linked into the list, and
+        // the list starting from `head` contains every index below its length.
+        let current = unsafe { &*subscriber };
+        if current.index == index {
+            return current;
+        }
+        subscriber = current.next;
+    }
+}
+
+impl Subscriber {
+    // Called while holding a guard of the registry, which an unloading library
+    // waits for after disabling its subscribers.
+    fn notify(&self, node: &'static Node) {
+        #[cfg(feature = "unload")]
+        if self.removed.load(Ordering::SeqCst) {
+            return;
+        }
+        unsafe { (self.call)(self.callback, node) }
+    }
+}
+
+// The subscriptions made through this copy of the crate, which lives in the
+// same executable or shared library as their callbacks, linked by `own_next`.
+#[cfg(feature = "unload")]
+static OWN: AtomicPtr<Subscriber> = AtomicPtr::new(ptr::null_mut());
+
+#[cfg(feature = "unload")]
+fn own(new: *mut Subscriber) {
+    let mut head = OWN.load(Ordering::Acquire);
+    loop {
+        unsafe { (*new).own_next = head };
+        match OWN.compare_exchange_weak(head, new, Ordering::AcqRel, Ordering::Acquire) {
+            Ok(_) => return,
+            Err(current) => head = current,
+        }
+    }
+}
+
+// Called by the destructor of this copy of the crate. See src/unload.rs.
+#[cfg(feature = "unload")]
+pub(crate) fn unsubscribe_own() {
+    let mut subscriber = OWN.load(Ordering::Acquire);
+    while let Some(current) = unsafe { subscriber.as_ref() } {
+        current
+            .registry
+            .quiesce(|| current.removed.store(true, Ordering::SeqCst));
+        subscriber = current.own_next;
+    }
+}
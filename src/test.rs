@@ -0,0 +1,147 @@
+//! Utilities for testing code that iterates plugin registries.
+
+use crate::{Collect, Constraints, ErasedNode, Node, Registry, SubmissionInfo};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicUsize, Ordering};
+
+/// Run a function during which the registry of `T` appears to contain only the
+/// given plugins, on the current thread.
+///
+/// Within `f`, [`inventory::iter::<T>`][crate::iter] and every other way of
+/// iterating or looking up plugins of type `T` visit exactly `entries`, in the
+/// order given, instead of the plugins that were submitted or registered.
+/// Other threads, including threads spawned by `f`, keep seeing the real
+/// contents of the registry, so tests run in parallel by the libtest harness
+/// do not affect one another. The real contents are visible again once `f`
+/// returns or panics. Calls may be nested, with the innermost one in effect.
+///
+/// Inside of `f` the [generation][crate::generation()] of the registry is a
+/// value unique to this call, so that caches keyed on the generation, such as
+/// [`Snapshot`][crate::Snapshot], are rebuilt both when entering and after
+/// leaving it. Plugins registered by `f` enter the real registry. A small
+/// allocation per entry is leaked.
+///
+/// # Examples
+///
+/// ```
+/// pub struct Handler {
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Handler);
+///
+/// inventory::submit!(Handler { name: "real" });
+///
+/// fn handler_names() -> Vec<&'static str> {
+///     inventory::iter::<Handler>
+///         .into_iter()
+///         .map(|handler| handler.name)
+///         .collect()
+/// }
+///
+/// static FAKE: Handler = Handler { name: "fake" };
+///
+/// fn main() {
+///     inventory::test::with_entries::<Handler>(&[], || {
+///         assert!(handler_names().is_empty());
+///     });
+///
+///     inventory::test::with_entries::<Handler>(&[&FAKE], || {
+///         assert_eq!(handler_names(), ["fake"]);
+///     });
+///
+///     assert_eq!(handler_names(), ["real"]);
+/// }
+/// ```
+pub fn with_entries<T: ?Sized + Collect>(entries: &[&'static T], f: impl FnOnce()) {
+    let nodes: &'static [Node] = Box::leak(
+        entries
+            .iter()
+            .map(|&value| Node {
+                value: Box::leak(Box::new(Substitute { value })),
+                next: AtomicPtr::new(ptr::null_mut()),
+                initialized: AtomicBool::new(true),
+                delivered: AtomicUsize::new(0),
+                enabled: AtomicBool::new(true),
+                info: SubmissionInfo::UNKNOWN,
+                priority: 0,
+                constraints: &Constraints::NONE,
+            })
+            .collect::<Vec<Node>>()
+            .into_boxed_slice(),
+    );
+    for pair in nodes.windows(2) {
+        let next = ptr::addr_of!(pair[1]).cast_mut();
+        pair[0].next.store(next, Ordering::Relaxed);
+    }
+
+    let registry: *const Registry = T::registry();
+    let substitution = Substitution {
+        registry,
+        head: nodes.first(),
+        generation: NEXT_GENERATION.fetch_add(1, Ordering::Relaxed),
+    };
+    SUBSTITUTIONS.with(|substitutions| substitutions.borrow_mut().push(substitution));
+    let _restore = Restore;
+    f();
+}
+
+// Removes the innermost substitution, even if the function given to
+// with_entries panics.
+struct Restore;
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        SUBSTITUTIONS.with(|substitutions| substitutions.borrow_mut().pop());
+    }
+}
+
+// The node through which an entry is visited. It is leaked, so the pointer
+// from Collect::erase stays valid even for trait objects, where it points to
+// the reference stored here.
+struct Substitute<T: ?Sized + 'static> {
+    value: &'static T,
+}
+
+impl<T: ?Sized + Collect> ErasedNode for Substitute<T> {
+    fn registry(&self) -> &'static Registry {
+        T::registry()
+    }
+
+    fn value(&self) -> *const () {
+        T::erase(&self.value)
+    }
+}
+
+#[derive(Copy, Clone)]
+pub(crate) struct Substitution {
+    registry: *const Registry,
+    pub(crate) head: Option<&'static Node>,
+    pub(crate) generation: usize,
+}
+
+std::thread_local! {
+    static SUBSTITUTIONS: RefCell<Vec<Substitution>> = const { RefCell::new(Vec::new()) };
+}
+
+// Generations reported while a substitution is in effect count up from halfway
+// through the range of usize, far from any that a real registry reaches.
+static NEXT_GENERATION: AtomicUsize = AtomicUsize::new(usize::MAX / 2 + 1);
+
+// The substitution in effect for the given registry on the current thread.
+pub(crate) fn substitution(registry: &Registry) -> Option<Substitution> {
+    SUBSTITUTIONS
+        .try_with(|substitutions| {
+            let substitutions = substitutions.borrow();
+            substitutions
+                .iter()
+                .rev()
+                .find(|substitution| ptr::eq(substitution.registry, registry))
+                .copied()
+        })
+        .ok()
+        .flatten()
+}
@@ -0,0 +1,348 @@
+use crate::{Collect, Element, Guard, IterSorted, Keyed, Node, SubmissionInfo};
+#[cfg(feature = "alloc")]
+use alloc::vec;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::any;
+use core::cmp;
+use core::fmt::{self, Debug, Display};
+use core::marker::PhantomData;
+use core::sync::atomic::Ordering;
+
+/// What to do when more than one plugin of a type declared with
+/// `inventory::collect!(T, unique_by = ...)` has the same key.
+///
+/// "First" and "last" refer to the order in which plugins are visited by
+/// [`iter_sorted`][crate::iter_sorted]: by priority, then by the location of
+/// their `submit!`. Plugins that have been turned off by
+/// [`disable`][crate::disable] do not count as duplicates, except to
+/// [`iter_all`][crate::iter_all], which applies the policy to all plugins.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OnDuplicate {
+    /// Panic upon iterating the registry. This is the default.
+    Panic,
+    /// Visit every plugin. Duplicates are only reported by [`try_iter`].
+    Error,
+    /// Visit only the first plugin with any particular key.
+    KeepFirst,
+    /// Visit only the last plugin with any particular key.
+    KeepLast,
+}
+
+// Not public API. Used by generated code.
+#[doc(hidden)]
+pub struct Unique<T: ?Sized> {
+    policy: OnDuplicate,
+    cmp_key: fn(&T, &T) -> cmp::Ordering,
+    fmt_key: fn(&T, &mut fmt::Formatter) -> fmt::Result,
+}
+
+impl<T: ?Sized + Keyed> Unique<T>
+where
+    T::Key: Debug,
+{
+    // Naming fmt_key::<T> directly inside of a const fn is rejected by older
+    // compilers because its signature contains a mutable reference.
+    const FMT_KEY: fn(&T, &mut fmt::Formatter) -> fmt::Result = fmt_key::<T>;
+
+    pub const fn new(policy: OnDuplicate) -> Self {
+        Unique {
+            policy,
+            cmp_key: cmp_key::<T>,
+            fmt_key: Self::FMT_KEY,
+        }
+    }
+}
+
+fn cmp_key<T: ?Sized + Keyed>(a: &T, b: &T) -> cmp::Ordering {
+    a.key().cmp(b.key())
+}
+
+fn fmt_key<T: ?Sized + Keyed>(plugin: &T, formatter: &mut fmt::Formatter) -> fmt::Result
+where
+    T::Key: Debug,
+{
+    Debug::fmt(plugin.key(), formatter)
+}
+
+impl<T: ?Sized + Collect> Unique<T> {
+    fn cmp_key(&self, a: Element, b: Element) -> cmp::Ordering {
+        unsafe { (self.cmp_key)(a.get::<T>(), b.get::<T>()) }
+    }
+
+    // Whether the policy leaves out some of the plugins that have duplicate
+    // keys.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn excludes(&self) -> bool {
+        self.policy == OnDuplicate::KeepFirst || self.policy == OnDuplicate::KeepLast
+    }
+
+    fn same_key(&self, a: Element, b: Element) -> bool {
+        self.cmp_key(a, b) == cmp::Ordering::Equal
+    }
+
+    // Whether an element with the same key comes before (`Less`) or after
+    // (`Greater`) this one in the order of iter_sorted. Unless `disabled` is
+    // set, plugins that have been disabled are not considered duplicates of
+    // any other plugin.
+    fn duplicate(
+        &self,
+        head: Option<&'static Node>,
+        element: Element,
+        disabled: bool,
+        side: cmp::Ordering,
+    ) -> bool {
+        let mut other = Element::first(head);
+        while let Some(current) = other {
+            if current.order(element) == side
+                && counts(current, disabled)
+                && self.same_key(current, element)
+            {
+                return true;
+            }
+            other = current.next();
+        }
+        false
+    }
+
+    fn earlier_duplicate(
+        &self,
+        head: Option<&'static Node>,
+        element: Element,
+        disabled: bool,
+    ) -> bool {
+        self.duplicate(head, element, disabled, cmp::Ordering::Less)
+    }
+
+    fn later_duplicate(
+        &self,
+        head: Option<&'static Node>,
+        element: Element,
+        disabled: bool,
+    ) -> bool {
+        self.duplicate(head, element, disabled, cmp::Ordering::Greater)
+    }
+
+    // Whether any two enabled plugins have the same key.
+    fn has_duplicates(&self, head: Option<&'static Node>) -> bool {
+        let mut element = Element::first(head);
+        while let Some(current) = element {
+            if counts(current, false) && self.later_duplicate(head, current, false) {
+                return true;
+            }
+            element = current.next();
+        }
+        false
+    }
+}
+
+fn counts(element: Element, disabled: bool) -> bool {
+    disabled || element.node.enabled.load(Ordering::Relaxed)
+}
+
+// Panic if T's policy says so and the registry contains duplicates. Only the
+// first iteration after each new registration performs the check.
+pub(crate) fn check<T: ?Sized + Collect>(generation: usize, head: Option<&'static Node>) {
+    let unique = match &T::UNIQUE {
+        Some(unique) if unique.policy == OnDuplicate::Panic => unique,
+        _ => return,
+    };
+    let registry = T::registry();
+    if registry.checked.load(Ordering::Acquire) == generation {
+        return;
+    }
+    assert!(
+        !unique.has_duplicates(head),
+        "{}",
+        DuplicateError::<T>::new(head),
+    );
+    registry.checked.store(generation, Ordering::Release);
+}
+
+// Remove the elements that T's policy does not let iteration visit. Rather than
+// searching the list for duplicates of each element, as `keep` does, this sorts
+// by key to find them all at once.
+#[cfg(feature = "alloc")]
+pub(crate) fn retain<T: ?Sized + Collect>(elements: &mut Vec<Element>) {
+    let (unique, keep_last) = match &T::UNIQUE {
+        Some(unique) => match unique.policy {
+            OnDuplicate::Panic | OnDuplicate::Error => return,
+            OnDuplicate::KeepFirst => (unique, false),
+            OnDuplicate::KeepLast => (unique, true),
+        },
+        None => return,
+    };
+    // Elements with equal keys in the order of iter_sorted.
+    let mut by_key: Vec<usize> = (0..elements.len()).collect();
+    by_key.sort_unstable_by(|&a, &b| {
+        unique
+            .cmp_key(elements[a], elements[b])
+            .then_with(|| elements[a].order(elements[b]))
+    });
+    let mut kept = vec![false; elements.len()];
+    for (i, &element) in by_key.iter().enumerate() {
+        let neighbor = if keep_last {
+            by_key.get(i + 1)
+        } else {
+            i.checked_sub(1).map(|i| &by_key[i])
+        };
+        kept[element] = neighbor.map_or(true, |&other| {
+            !unique.same_key(elements[element], elements[other])
+        });
+    }
+    let mut kept = kept.into_iter();
+    elements.retain(|_| kept.next().unwrap());
+}
+
+// Whether T's policy lets iteration visit this element, where `disabled`
+// means that the iteration includes disabled plugins.
+pub(crate) fn keep<T: ?Sized + Collect>(
+    head: Option<&'static Node>,
+    element: Element,
+    disabled: bool,
+) -> bool {
+    match &T::UNIQUE {
+        Some(unique) => match unique.policy {
+            OnDuplicate::Panic | OnDuplicate::Error => true,
+            OnDuplicate::KeepFirst => !unique.earlier_duplicate(head, element, disabled),
+            OnDuplicate::KeepLast => !unique.later_duplicate(head, element, disabled),
+        },
+        None => true,
+    }
+}
+
+/// Iterate over plugins registered of a given type, or report duplicate keys.
+///
+/// For a type declared with `inventory::collect!(T, unique_by = ...)` and
+/// [`OnDuplicate::Panic`] or [`OnDuplicate::Error`], this returns an error
+/// describing every set of plugins that share a key, instead of panicking or
+/// visiting all of them. Otherwise it is equivalent to
+/// [`iter_sorted`][crate::iter_sorted].
+///
+/// # Errors
+///
+/// Returns an error if the registry contains more than one plugin with the
+/// same key, and the registry's policy is [`OnDuplicate::Panic`] or
+/// [`OnDuplicate::Error`].
+///
+/// # Examples
+///
+/// ```
+/// pub struct Flag {
+///     short: char,
+///     name: &'static str,
+/// }
+///
+/// inventory::collect!(Flag, unique_by = name: str, on_duplicate = Error);
+///
+/// inventory::submit!(Flag { short: 'v', name: "verbose" });
+/// inventory::submit!(Flag { short: 'V', name: "verbose" });
+///
+/// fn main() {
+///     match inventory::try_iter::<Flag>() {
+///         Ok(flags) => {
+///             for flag in flags {
+///                 println!("-{}, --{}", flag.short, flag.name);
+///             }
+///         }
+///         Err(error) => {
+///             for (flag, info) in error.duplicates() {
+///                 eprintln!("--{} registered at {}", flag.name, info);
+///             }
+///         }
+///     }
+/// }
+/// ```
+pub fn try_iter<T: ?Sized + Collect>() -> Result<IterSorted<T>, DuplicateError<T>> {
+    let iter = IterSorted::unchecked();
+    match &T::UNIQUE {
+        Some(unique)
+            if (unique.policy == OnDuplicate::Panic || unique.policy == OnDuplicate::Error)
+                && unique.has_duplicates(iter.head()) =>
+        {
+            Err(DuplicateError::new(iter.head()))
+        }
+        _ => Ok(iter),
+    }
+}
+
+/// Error returned by [`try_iter`] when plugins have duplicate keys.
+///
+/// Displays as a list of every duplicated key, with the locations where it was
+/// registered.
+pub struct DuplicateError<T: ?Sized + 'static> {
+    _guard: Guard,
+    head: Option<&'static Node>,
+    marker: PhantomData<T>,
+}
+
+impl<T: ?Sized + Collect> DuplicateError<T> {
+    fn new(head: Option<&'static Node>) -> Self {
+        DuplicateError {
+            _guard: Guard::new(T::registry()),
+            head,
+            marker: PhantomData,
+        }
+    }
+
+    fn unique() -> &'static Unique<T> {
+        // Never constructed for a type without a Unique policy.
+        T::UNIQUE.as_ref().unwrap()
+    }
+
+    /// Every plugin whose key is shared with some other plugin, in iteration
+    /// order, together with where it was registered.
+    pub fn duplicates(&self) -> impl Iterator<Item = (&'static T, &'static SubmissionInfo)> + '_ {
+        let unique = Self::unique();
+        let head = self.head;
+        let mut element = Element::first(head);
+        core::iter::from_fn(move || loop {
+            let current = element?;
+            element = current.next();
+            if counts(current, false)
+                && (unique.earlier_duplicate(head, current, false)
+                    || unique.later_duplicate(head, current, false))
+            {
+                return Some((unsafe { current.get::<T>() }, &current.node.info));
+            }
+        })
+    }
+}
+
+impl<T: ?Sized + Collect> Display for DuplicateError<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let unique = Self::unique();
+        write!(
+            formatter,
+            "duplicate keys among plugins of type `{}`:",
+            any::type_name::<T>(),
+        )?;
+        let mut element = Element::first(self.head);
+        while let Some(first) = element {
+            element = first.next();
+            if !counts(first, false)
+                || unique.earlier_duplicate(self.head, first, false)
+                || !unique.later_duplicate(self.head, first, false)
+            {
+                continue;
+            }
+            formatter.write_str("\n    ")?;
+            (unique.fmt_key)(unsafe { first.get::<T>() }, formatter)?;
+            write!(formatter, " registered at {}", first.node.info)?;
+            let mut other = Element::first(self.head);
+            while let Some(current) = other {
+                if !current.is(first) && counts(current, false) && unique.same_key(first, current) {
+                    write!(formatter, ", {}", current.node.info)?;
+                }
+                other = current.next();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: ?Sized + Collect> Debug for DuplicateError<T> {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(self, formatter)
+    }
+}
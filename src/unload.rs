@@ -0,0 +1,253 @@
+// With the "unload" feature, on ELF targets, every `submit!` produces a
+// destructor in addition to its constructor. When a shared library is unloaded
+// by dlclose, the destructors of its submissions unlink their nodes from
+// whatever registry they were linked into, possibly a registry belonging to the
+// executable, before the library's memory is unmapped.
+//
+// Nodes can be removed while other threads are traversing the same list, so
+// every traversal holds a Guard for as long as it may dereference a node. The
+// guards are counted in one of two slots of the registry, chosen by the parity
+// of the registry's epoch. This state lives in the registry rather than in
+// statics of this crate because a registry may be shared by an executable and
+// the libraries it loads, each of which contains its own copy of this crate.
+// To remove a node, it is first unlinked so that no traversal starting
+// from then on can reach it, then the epoch is advanced so that new guards are
+// counted in the other slot, and then the destructor waits for the slot of the
+// previous epoch to drain. Only one node of a registry is removed at a time.
+//
+// The destructors also run when the process exits, when no memory is about to
+// be unmapped and other threads may hold guards forever. So the first
+// submission or subscription through each copy of this crate registers an exit
+// handler with __cxa_atexit, which libc runs before any destructor in
+// .fini_array at exit, and which dlclose of the library that contains it runs
+// only after them. Once it has run, destructors do nothing.
+//
+// Subscriptions made through a copy of this crate are disabled by a destructor
+// of that copy, in the same way as nodes are removed: the subscriber is marked,
+// and then the destructor waits for the guards under which callbacks are
+// called, so that none is running when the library is unmapped.
+//
+// A destructor waits for guards of every thread, including the one running
+// it, which is the thread that called dlclose. So dlclose from inside of an
+// iteration of a registry that the library submitted to waits for an iterator
+// that is never dropped, and does not return.
+//
+// While a node is being removed, the low bit of its next pointer is set, so
+// that a concurrent insertion does not link a new node after it.
+
+use crate::{Node, Registry};
+use core::hint;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+pub(crate) struct Guard {
+    registry: &'static Registry,
+    slot: usize,
+}
+
+impl Guard {
+    pub(crate) fn new(registry: &'static Registry) -> Self {
+        loop {
+            let epoch = registry.epoch.load(Ordering::SeqCst);
+            let slot = epoch % 2;
+            registry.readers[slot].fetch_add(1, Ordering::SeqCst);
+            // If the epoch advanced in the meantime, a removal may already be
+            // waiting on the other slot without having seen this guard.
+            if registry.epoch.load(Ordering::SeqCst) == epoch {
+                return Guard { registry, slot };
+            }
+            registry.readers[slot].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Clone for Guard {
+    fn clone(&self) -> Self {
+        // The slot is already held open by self.
+        self.registry.readers[self.slot].fetch_add(1, Ordering::SeqCst);
+        Guard {
+            registry: self.registry,
+            slot: self.slot,
+        }
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        self.registry.readers[self.slot].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Registry {
+    pub(crate) fn remove(&'static self, node: &'static Node) {
+        if !node.initialized.load(Ordering::Relaxed) || EXITING.load(Ordering::Acquire) {
+            return;
+        }
+
+        self.quiesce(|| {
+            let mut next = node.next.load(Ordering::Acquire);
+            while let Err(current) = node.next.compare_exchange_weak(
+                next,
+                mark(next),
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                next = current;
+            }
+
+            self.unlink(node, next);
+            self.generation.fetch_add(1, Ordering::Release);
+        });
+    }
+
+    // Runs `f`, then waits for every guard that was acquired before it ran to
+    // be dropped.
+    pub(crate) fn quiesce(&'static self, f: impl FnOnce()) {
+        while self
+            .removing
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+
+        f();
+
+        let slot = self.epoch.fetch_add(1, Ordering::SeqCst) % 2;
+        while self.readers[slot].load(Ordering::SeqCst) != 0 && !EXITING.load(Ordering::Acquire) {
+            hint::spin_loop();
+        }
+
+        self.removing.store(false, Ordering::Release);
+    }
+
+    fn unlink(&'static self, node: &'static Node, next: *mut Node) {
+        let _guard = Guard::new(self);
+        let node_ptr = ptr::addr_of!(*node).cast_mut();
+        'search: loop {
+            let mut link = &self.head;
+            loop {
+                let current = link.load(Ordering::Acquire);
+                if current == node_ptr {
+                    // Fails only if a node was inserted right before this one
+                    // since loading `current`.
+                    if link
+                        .compare_exchange(current, next, Ordering::AcqRel, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        return;
+                    }
+                    continue 'search;
+                }
+                // Nodes that are not being removed have unmarked next pointers.
+                match unsafe { current.as_ref() } {
+                    Some(current) => link = &current.next,
+                    None => return,
+                }
+            }
+        }
+    }
+}
+
+fn mark(next: *mut Node) -> *mut Node {
+    next.cast::<u8>().wrapping_add(1).cast()
+}
+
+pub(crate) fn is_marked(next: *mut Node) -> bool {
+    next as usize & 1 != 0
+}
+
+pub(crate) fn unmark(next: *mut Node) -> *mut Node {
+    next.cast::<u8>().wrapping_sub(next as usize & 1).cast()
+}
+
+// Set by the exit handler of this copy of the crate.
+static EXITING: AtomicBool = AtomicBool::new(false);
+
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+// Called by every submission and subscription, to register the exit handler
+// once.
+pub(crate) fn watch_exit() {
+    if !WATCHING.swap(true, Ordering::Relaxed) {
+        sys::at_exit(on_exit);
+    }
+}
+
+extern "C" fn on_exit(_arg: *mut u8) {
+    EXITING.store(true, Ordering::Release);
+}
+
+#[cfg(all(
+    not(target_family = "wasm"),
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "haiku",
+        target_os = "illumos",
+        target_os = "netbsd",
+        target_os = "nto",
+        target_os = "openbsd",
+        target_os = "vxworks",
+    ),
+))]
+mod sys {
+    use core::ptr;
+    #[cfg(feature = "alloc")]
+    use core::sync::atomic::Ordering;
+
+    extern "C" {
+        static __dso_handle: *mut u8;
+        fn __cxa_atexit(f: extern "C" fn(*mut u8), arg: *mut u8, dso: *mut u8) -> i32;
+    }
+
+    // Subscriptions call into the executable or library that made them, so
+    // this copy of the crate has a destructor of its own that disables them
+    // when the library that contains it is unloaded.
+    #[cfg(feature = "alloc")]
+    #[used]
+    #[link_section = ".fini_array"]
+    static UNSUBSCRIBE: extern "C" fn() = unsubscribe;
+
+    #[cfg(feature = "alloc")]
+    extern "C" fn unsubscribe() {
+        if !super::EXITING.load(Ordering::Acquire) {
+            crate::subscribe::unsubscribe_own();
+        }
+    }
+
+    // Registered for the library or executable that contains this copy of the
+    // crate, so that dlclose runs it rather than leaving it dangling.
+    pub(super) fn at_exit(f: extern "C" fn(*mut u8)) {
+        unsafe {
+            __cxa_atexit(
+                f,
+                ptr::null_mut(),
+                ptr::addr_of!(__dso_handle).cast_mut().cast(),
+            );
+        }
+    }
+}
+
+// Targets without a libc, which neither exit nor unload libraries, and targets
+// on which no destructors are produced.
+#[cfg(not(all(
+    not(target_family = "wasm"),
+    any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "haiku",
+        target_os = "illumos",
+        target_os = "netbsd",
+        target_os = "nto",
+        target_os = "openbsd",
+        target_os = "vxworks",
+    ),
+)))]
+mod sys {
+    pub(super) fn at_exit(_f: extern "C" fn(*mut u8)) {}
+}
@@ -0,0 +1,82 @@
+use crate::{Element, Registry};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::hint;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+// Elements of a registry computed from its list of nodes, such as the sorted
+// index searched by inventory::get, as of some generation of the registry.
+pub(crate) struct View {
+    pub(crate) generation: usize,
+    pub(crate) elements: Vec<Element>,
+}
+
+// The most recently computed view of one kind for a registry. Views are
+// reference counted so that a view replaced by a newer one is freed as soon as
+// the last thread still using it is done with it. The lock is only held to
+// clone or replace the Arc, never while building a view.
+pub(crate) struct Cache {
+    locked: AtomicBool,
+    view: UnsafeCell<Option<Arc<View>>>,
+}
+
+// The view is only accessed while holding the lock.
+unsafe impl Sync for Cache {}
+
+impl Cache {
+    pub(crate) const fn new() -> Self {
+        Cache {
+            locked: AtomicBool::new(false),
+            view: UnsafeCell::new(None),
+        }
+    }
+
+    // The cached view if it was computed at the current generation of the
+    // registry that this cache belongs to, otherwise a new one computed by
+    // `build`, which replaces the cached one.
+    pub(crate) fn get(
+        &self,
+        registry: &Registry,
+        build: impl FnOnce() -> Vec<Element>,
+    ) -> Arc<View> {
+        let generation = registry.generation();
+
+        // A view of the entries substituted by inventory::test on the current
+        // thread must never be found by another thread, so it is not cached.
+        #[cfg(feature = "test-util")]
+        if crate::test::substitution(registry).is_some() {
+            return Arc::new(View {
+                generation,
+                elements: build(),
+            });
+        }
+
+        if let Some(view) = self.with(|view| view.clone()) {
+            if view.generation == generation {
+                return view;
+            }
+        }
+        let view = Arc::new(View {
+            generation,
+            elements: build(),
+        });
+        let replaced = self.with(|cached| cached.replace(Arc::clone(&view)));
+        // Dropped outside of the lock.
+        drop(replaced);
+        view
+    }
+
+    fn with<R>(&self, f: impl FnOnce(&mut Option<Arc<View>>) -> R) -> R {
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            hint::spin_loop();
+        }
+        let result = f(unsafe { &mut *self.view.get() });
+        self.locked.store(false, Ordering::Release);
+        result
+    }
+}
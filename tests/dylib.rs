@@ -0,0 +1,77 @@
+#![cfg(target_os = "linux")]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Once;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Builds the host executable and plugin library in tests/dylib, and returns the
+// directory containing them.
+fn build() -> PathBuf {
+    static BUILD: Once = Once::new();
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/dylib");
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("dylib");
+    BUILD.call_once(|| {
+        let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+        let status = Command::new(cargo)
+            .arg("build")
+            .arg("--manifest-path")
+            .arg(fixture.join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(&target_dir)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    });
+    target_dir.join("debug")
+}
+
+// Runs one test in the host executable, which must exit successfully within a
+// time limit, as some of them check that nothing hangs at exit.
+fn run_host(test: &str) {
+    let dir = build();
+    let mut child = Command::new(dir.join("dylib-host"))
+        .arg(test)
+        .arg(dir.join("libdylib_plugin.so"))
+        .spawn()
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(60);
+    let status = loop {
+        if let Some(status) = child.try_wait().unwrap() {
+            break status;
+        }
+        if Instant::now() > deadline {
+            child.kill().unwrap();
+            panic!("dylib-host {test} timed out");
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+    assert!(status.success());
+}
+
+#[test]
+fn test_dlopen() {
+    run_host("dlopen");
+}
+
+#[test]
+fn test_dlclose() {
+    run_host("dlclose");
+}
+
+#[test]
+fn test_exit() {
+    run_host("exit");
+}
+
+#[test]
+fn test_exit_thread() {
+    run_host("exit-thread");
+}
+
+#[test]
+fn test_dlclose_subscribed() {
+    run_host("dlclose-subscribed");
+}
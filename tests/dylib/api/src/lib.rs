@@ -0,0 +1,5 @@
+pub struct Plugin {
+    pub name: &'static str,
+}
+
+inventory::collect!(Plugin, export = "dylib_api_plugins");
@@ -0,0 +1,5 @@
+fn main() {
+    // Export the registry symbol from the executable, for plugin libraries to
+    // bind to.
+    println!("cargo:rustc-link-arg-bins=-rdynamic");
+}
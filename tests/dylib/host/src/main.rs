@@ -0,0 +1,164 @@
+use dylib_api::Plugin;
+use std::env;
+use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::mem;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+// Visited ahead of the library's plugin, which is registered later.
+inventory::submit! {
+    #![priority(1)]
+    Plugin { name: "host" }
+}
+
+#[link(name = "dl")]
+extern "C" {
+    fn dlopen(filename: *const c_char, flags: c_int) -> *mut c_void;
+    fn dlsym(handle: *mut c_void, symbol: *const c_char) -> *mut c_void;
+    fn dlclose(handle: *mut c_void) -> c_int;
+    fn dlerror() -> *const c_char;
+}
+
+const RTLD_NOW: c_int = 2;
+
+struct Library(*mut c_void);
+
+impl Library {
+    fn open(path: &CStr) -> Self {
+        let handle = unsafe { dlopen(path.as_ptr(), RTLD_NOW) };
+        if handle.is_null() {
+            panic!("{}", unsafe { CStr::from_ptr(dlerror()) }.to_string_lossy());
+        }
+        Library(handle)
+    }
+
+    fn count_fn(&self) -> extern "C" fn() -> usize {
+        self.function(c"dylib_plugin_count")
+    }
+
+    fn subscribe_fn(&self) -> extern "C" fn() -> usize {
+        self.function(c"dylib_plugin_subscribe")
+    }
+
+    fn function(&self, name: &CStr) -> extern "C" fn() -> usize {
+        let symbol = unsafe { dlsym(self.0, name.as_ptr()) };
+        assert!(!symbol.is_null());
+        unsafe { mem::transmute(symbol) }
+    }
+}
+
+impl Drop for Library {
+    fn drop(&mut self) {
+        assert_eq!(unsafe { dlclose(self.0) }, 0);
+    }
+}
+
+fn names() -> Vec<&'static str> {
+    inventory::iter::<Plugin>
+        .into_iter()
+        .map(|plugin| plugin.name)
+        .collect()
+}
+
+static SUBSCRIBED: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+fn on_plugin(plugin: &'static Plugin) {
+    SUBSCRIBED.lock().unwrap().push(plugin.name);
+}
+
+fn test_dlopen(path: &CStr) {
+    inventory::subscribe::<Plugin>(on_plugin);
+    let generation = inventory::generation::<Plugin>();
+    assert_eq!(names(), ["host"]);
+
+    let library = Library::open(path);
+    assert_eq!(names(), ["host", "plugin"]);
+    assert_ne!(inventory::generation::<Plugin>(), generation);
+    assert_eq!(*SUBSCRIBED.lock().unwrap(), ["host", "plugin"]);
+
+    // The library iterates the same registry as the executable.
+    assert_eq!(library.count_fn()(), 2);
+}
+
+fn test_dlclose(path: &CStr) {
+    assert_eq!(names(), ["host"]);
+
+    let library = Library::open(path);
+    assert_eq!(names(), ["host", "plugin"]);
+    drop(library);
+    assert_eq!(names(), ["host"]);
+
+    // Other threads keep iterating, and reading each plugin's name out of
+    // whichever library it lives in, while the library is loaded and unloaded.
+    let done = AtomicBool::new(false);
+    thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    for plugin in inventory::iter::<Plugin> {
+                        assert!(plugin.name == "host" || plugin.name == "plugin");
+                    }
+                }
+            });
+        }
+        for _ in 0..100 {
+            drop(Library::open(path));
+        }
+        done.store(true, Ordering::Relaxed);
+    });
+    assert_eq!(names(), ["host"]);
+}
+
+// Exiting while this thread iterates the registry returns promptly, rather
+// than waiting in the destructors for the iterator to be dropped.
+fn test_exit(path: &CStr) {
+    let library = Library::open(path);
+    for _plugin in inventory::iter::<Plugin> {
+        process::exit(0);
+    }
+    drop(library);
+    unreachable!();
+}
+
+// Likewise when another thread holds an iterator that is never dropped.
+fn test_exit_thread(path: &CStr) {
+    // Unloaded only by the exit.
+    mem::forget(Library::open(path));
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let iter = inventory::iter::<Plugin>.into_iter();
+        sender.send(()).unwrap();
+        let _keep = iter;
+        loop {
+            thread::park();
+        }
+    });
+    receiver.recv().unwrap();
+}
+
+// A subscription made by the library is not called after the library has been
+// unloaded, when its callback no longer exists.
+fn test_dlclose_subscribed(path: &CStr) {
+    let library = Library::open(path);
+    assert_eq!(library.subscribe_fn()(), 2);
+    drop(library);
+
+    inventory::register_boxed(Box::new(Plugin { name: "boxed" }));
+    assert_eq!(names(), ["host", "boxed"]);
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let test = args.next().unwrap();
+    let path = CString::new(args.next().unwrap()).unwrap();
+    match test.as_str() {
+        "dlopen" => test_dlopen(&path),
+        "dlclose" => test_dlclose(&path),
+        "exit" => test_exit(&path),
+        "exit-thread" => test_exit_thread(&path),
+        "dlclose-subscribed" => test_dlclose_subscribed(&path),
+        _ => panic!("unknown test: {}", test),
+    }
+}
@@ -0,0 +1,23 @@
+use dylib_api::Plugin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+inventory::submit!(Plugin { name: "plugin" });
+
+#[no_mangle]
+pub extern "C" fn dylib_plugin_count() -> usize {
+    inventory::iter::<Plugin>.into_iter().count()
+}
+
+static SUBSCRIBED: AtomicUsize = AtomicUsize::new(0);
+
+fn on_plugin(_plugin: &'static Plugin) {
+    SUBSCRIBED.fetch_add(1, Ordering::Relaxed);
+}
+
+// Subscribes to the executable's registry, and returns how many plugins the
+// subscription has been called for so far.
+#[no_mangle]
+pub extern "C" fn dylib_plugin_subscribe() -> usize {
+    inventory::subscribe::<Plugin>(on_plugin);
+    SUBSCRIBED.load(Ordering::Relaxed)
+}
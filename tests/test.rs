@@ -7,3 +7,1093 @@ fn test_iter() {
     assert_eq!(0, mem::size_of::<inventory::iter<Thing>>());
     assert_eq!(1, mem::align_of::<inventory::iter<Thing>>());
 }
+
+#[cfg(all(feature = "section", target_os = "linux"))]
+mod section {
+    pub struct Sectioned(pub usize);
+
+    inventory::collect!(Sectioned);
+
+    inventory::submit!(Sectioned(1));
+    inventory::submit!(Sectioned(2));
+
+    extern "Rust" {
+        #[link_name = "__start_inventory_0_3"]
+        pub static START: [*const (); 0];
+        #[link_name = "__stop_inventory_0_3"]
+        pub static STOP: [*const (); 0];
+    }
+}
+
+#[cfg(all(feature = "section", target_os = "linux"))]
+#[test]
+fn test_section() {
+    use section::Sectioned;
+
+    let mut values: Vec<usize> = inventory::iter::<Sectioned>
+        .into_iter()
+        .map(|sectioned| sectioned.0)
+        .collect();
+    values.sort_unstable();
+    assert_eq!(values, [1, 2]);
+
+    let entries = unsafe {
+        let start = std::ptr::addr_of!(section::START).cast::<*const ()>();
+        let stop = std::ptr::addr_of!(section::STOP).cast::<*const ()>();
+        let len = (stop as usize - start as usize) / mem::size_of::<*const ()>();
+        std::slice::from_raw_parts(start, len)
+    };
+    assert!(entries.len() >= 2);
+    assert!(entries.iter().all(|entry| !entry.is_null()));
+}
+
+pub struct Runtime(pub &'static str);
+
+inventory::collect!(Runtime);
+
+inventory::submit!(Runtime("static"));
+
+static ENTRY: inventory::Entry<Runtime> = inventory::Entry::new(&Runtime("entry"));
+
+#[test]
+fn test_register() {
+    inventory::register(&ENTRY);
+    inventory::register(&ENTRY);
+
+    let mut values: Vec<&str> = inventory::iter::<Runtime>
+        .into_iter()
+        .map(|runtime| runtime.0)
+        .collect();
+    values.sort_unstable();
+    assert_eq!(values, ["entry", "static"]);
+}
+
+pub struct Lazy(pub String);
+
+inventory::collect!(Lazy);
+
+inventory::submit_lazy!(Lazy, Lazy("lazy".repeat(2)));
+
+#[test]
+fn test_submit_lazy() {
+    let first = inventory::iter::<Lazy>.into_iter().next().unwrap();
+    let second = inventory::iter::<Lazy>.into_iter().next().unwrap();
+    assert_eq!(first.0, "lazylazy");
+    assert!(std::ptr::eq(first, second));
+}
+
+pub struct Sorted(pub u32);
+
+inventory::collect!(Sorted);
+
+mod sorted {
+    use crate::Sorted;
+
+    inventory::submit!(Sorted(3));
+}
+
+inventory::submit!(Sorted(1));
+inventory::submit!(Sorted(2));
+
+#[test]
+fn test_iter_sorted() {
+    let values: Vec<u32> = inventory::iter_sorted::<Sorted>()
+        .map(|sorted| sorted.0)
+        .collect();
+    assert_eq!(values, [1, 2, 3]);
+}
+
+pub struct Prioritized(pub &'static str);
+
+inventory::collect!(Prioritized);
+
+inventory::submit!(Prioritized("default"));
+
+inventory::submit! {
+    #![priority(-1)]
+    Prioritized("low")
+}
+
+inventory::submit! {
+    #![priority(10)]
+    Prioritized("high")
+}
+
+inventory::submit!(Prioritized("default 2"));
+
+#[test]
+fn test_priority() {
+    let values: Vec<&str> = inventory::iter::<Prioritized>
+        .into_iter()
+        .map(|prioritized| prioritized.0)
+        .collect();
+    assert_eq!(values.len(), 4);
+    assert_eq!(values[0], "high");
+    assert_eq!(values[3], "low");
+
+    let values: Vec<&str> = inventory::iter_sorted::<Prioritized>()
+        .map(|prioritized| prioritized.0)
+        .collect();
+    assert_eq!(values, ["high", "default", "default 2", "low"]);
+}
+
+#[test]
+fn test_iter_with_meta() {
+    let (prioritized, info) = inventory::iter_with_meta::<Prioritized>().next().unwrap();
+    assert_eq!(prioritized.0, "high");
+    assert_eq!(info.crate_name(), "test");
+    assert_eq!(info.module_path(), "test");
+    assert_eq!(info.file(), file!());
+    assert_eq!(info.to_string(), format!("{}:{}:1", file!(), info.line()));
+}
+
+pub struct Keyed {
+    pub name: &'static str,
+    pub value: u32,
+}
+
+inventory::collect!(Keyed, key = name: str);
+
+inventory::submit!(Keyed {
+    name: "a",
+    value: 1,
+});
+
+inventory::submit! {
+    #![priority(1)]
+    Keyed { name: "b", value: 2 }
+}
+
+inventory::submit!(Keyed {
+    name: "b",
+    value: 3,
+});
+
+static KEYED_ENTRY: inventory::Entry<Keyed> = inventory::Entry::new(&Keyed {
+    name: "c",
+    value: 4,
+});
+
+#[test]
+fn test_get() {
+    assert_eq!(inventory::get::<Keyed>("a").unwrap().value, 1);
+    assert_eq!(inventory::get::<Keyed>("b").unwrap().value, 2);
+    assert!(inventory::get::<Keyed>("c").is_none());
+
+    inventory::register(&KEYED_ENTRY);
+    assert_eq!(inventory::get::<Keyed>("c").unwrap().value, 4);
+}
+
+pub mod unique {
+    pub struct Panic(pub &'static str);
+    pub struct Error(pub &'static str);
+    pub struct KeepFirst(pub &'static str, pub u32);
+    pub struct KeepLast(pub &'static str, pub u32);
+
+    inventory::collect!(Panic, unique_by = 0: str);
+    inventory::collect!(Error, unique_by = 0: str, on_duplicate = Error);
+    inventory::collect!(KeepFirst, unique_by = 0: str, on_duplicate = KeepFirst);
+    inventory::collect!(KeepLast, unique_by = 0: str, on_duplicate = KeepLast);
+
+    inventory::submit!(Panic("a"));
+    inventory::submit!(Panic("b"));
+    inventory::submit!(Panic("a"));
+
+    inventory::submit!(Error("a"));
+    inventory::submit!(Error("b"));
+    inventory::submit!(Error("a"));
+
+    inventory::submit!(KeepFirst("a", 1));
+    inventory::submit!(KeepFirst("b", 2));
+    inventory::submit!(KeepFirst("a", 3));
+
+    inventory::submit!(KeepLast("a", 1));
+    inventory::submit!(KeepLast("b", 2));
+    inventory::submit!(KeepLast("a", 3));
+}
+
+#[test]
+#[should_panic = "duplicate keys among plugins of type `test::unique::Panic`:\n    \"a\" registered at tests/test.rs:"]
+fn test_unique_panic() {
+    let _ = inventory::iter::<unique::Panic>.into_iter();
+}
+
+#[test]
+fn test_unique_error() {
+    assert_eq!(inventory::iter::<unique::Error>.into_iter().count(), 3);
+
+    let error = inventory::try_iter::<unique::Error>().err().unwrap();
+    let duplicates: Vec<&str> = error.duplicates().map(|(error, _info)| error.0).collect();
+    assert_eq!(duplicates, ["a", "a"]);
+    assert!(inventory::try_iter::<unique::Panic>().is_err());
+}
+
+#[test]
+fn test_unique_keep() {
+    let first: Vec<u32> = inventory::iter_sorted::<unique::KeepFirst>()
+        .map(|keep| keep.1)
+        .collect();
+    assert_eq!(first, [1, 2]);
+
+    let last: Vec<u32> = inventory::try_iter::<unique::KeepLast>()
+        .unwrap()
+        .map(|keep| keep.1)
+        .collect();
+    assert_eq!(last, [2, 3]);
+}
+
+pub trait Plugin: Sync {
+    fn name(&self) -> &'static str;
+}
+
+inventory::collect!(dyn Plugin);
+
+pub struct Builtin;
+
+impl Plugin for Builtin {
+    fn name(&self) -> &'static str {
+        "builtin"
+    }
+}
+
+pub struct Named(pub &'static str);
+
+impl Plugin for Named {
+    fn name(&self) -> &'static str {
+        self.0
+    }
+}
+
+static EXTERNAL: Named = Named("external");
+
+inventory::submit!(Builtin as dyn Plugin);
+inventory::submit!(&EXTERNAL as &dyn Plugin);
+
+inventory::submit! {
+    #![priority(1)]
+    Named("first") as dyn Plugin
+}
+
+#[test]
+fn test_dyn() {
+    let names: Vec<&str> = inventory::iter_sorted::<dyn Plugin>()
+        .map(Plugin::name)
+        .collect();
+    assert_eq!(names, ["first", "builtin", "external"]);
+
+    let external = inventory::iter_sorted::<dyn Plugin>().last().unwrap();
+    assert!(std::ptr::addr_eq(external, &EXTERNAL));
+}
+
+#[cfg(feature = "alloc")]
+mod generic {
+    use std::marker::PhantomData;
+
+    pub trait Event {}
+
+    pub struct Click;
+    pub struct Key;
+    pub struct Scroll;
+
+    impl Event for Click {}
+    impl Event for Key {}
+    impl Event for Scroll {}
+
+    pub struct Handler<E> {
+        pub name: &'static str,
+        pub event: PhantomData<fn(E)>,
+    }
+
+    impl<E> Handler<E> {
+        pub const fn new(name: &'static str) -> Self {
+            Handler {
+                name,
+                event: PhantomData,
+            }
+        }
+    }
+
+    inventory::collect!(impl<E: Event> for Handler<E>);
+
+    inventory::submit!(Handler::<Click>::new("click"));
+    inventory::submit!(Handler::<Click>::new("double click"));
+    inventory::submit!(Handler::<Key>::new("key"));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_generic() {
+    use generic::{Click, Handler, Key, Scroll};
+
+    let click: Vec<&str> = inventory::iter_sorted::<Handler<Click>>()
+        .map(|handler| handler.name)
+        .collect();
+    let key: Vec<&str> = inventory::iter::<Handler<Key>>
+        .into_iter()
+        .map(|handler| handler.name)
+        .collect();
+    assert_eq!(click, ["click", "double click"]);
+    assert_eq!(key, ["key"]);
+    assert_eq!(inventory::iter::<Handler<Scroll>>.into_iter().count(), 0);
+}
+
+pub struct Flag(pub &'static str);
+
+inventory::collect!(Flag as ADMIN_FLAGS);
+inventory::collect!(Flag as pub USER_FLAGS);
+
+inventory::submit!(in ADMIN_FLAGS => Flag("force"));
+inventory::submit!(in USER_FLAGS => Flag("verbose"));
+
+inventory::submit! {
+    #![priority(1)]
+    in crate::ADMIN_FLAGS => Flag("yes")
+}
+
+#[test]
+fn test_iter_in() {
+    let admin: Vec<&str> = inventory::iter_in(&ADMIN_FLAGS)
+        .map(|flag| flag.0)
+        .collect();
+    let user: Vec<&str> = inventory::iter_in(&USER_FLAGS).map(|flag| flag.0).collect();
+    assert_eq!(admin, ["yes", "force"]);
+    assert_eq!(user, ["verbose"]);
+}
+
+#[cfg(feature = "macros")]
+mod register {
+    pub struct Command {
+        pub name: &'static str,
+        pub doc: &'static str,
+        pub run: fn() -> u32,
+    }
+
+    inventory::collect!(Command);
+
+    /// Compile the current package.
+    ///
+    /// Without running it.
+    #[inventory::register(Command, name, doc, run)]
+    fn build() -> u32 {
+        1
+    }
+
+    #[inventory::register(Command, name = "tidy", doc = "", run)]
+    fn clippy() -> u32 {
+        2
+    }
+
+    #[inventory::register(Command, name = "test", doc = "")]
+    fn test() -> u32 {
+        3
+    }
+
+    use ::inventory as renamed;
+
+    #[inventory::register(Command, crate = self::renamed, name, doc = "")]
+    fn vendor() -> u32 {
+        4
+    }
+
+    pub struct Typed<T> {
+        pub run: fn() -> T,
+    }
+
+    inventory::collect!(Typed<u32>);
+
+    #[inventory::register(Typed<u32>)]
+    fn typed() -> u32 {
+        5
+    }
+
+    pub struct Setting(pub &'static str);
+
+    inventory::collect!(Setting);
+
+    #[inventory::register(Setting)]
+    pub static COLOR: Setting = Setting("color");
+
+    pub trait Format: Sync {
+        fn extension(&self) -> &'static str;
+    }
+
+    inventory::collect!(dyn Format);
+
+    pub struct Json;
+
+    #[inventory::register(dyn Format)]
+    impl Format for Json {
+        fn extension(&self) -> &'static str {
+            "json"
+        }
+    }
+}
+
+#[cfg(feature = "macros")]
+#[test]
+fn test_register_attribute() {
+    use register::{Command, Format, Setting, Typed, COLOR};
+
+    let commands: Vec<(&str, &str, u32)> = inventory::iter_sorted::<Command>()
+        .map(|command| (command.name, command.doc, (command.run)()))
+        .collect();
+    assert_eq!(
+        commands,
+        [
+            (
+                "build",
+                "Compile the current package.\n\nWithout running it.",
+                1
+            ),
+            ("tidy", "", 2),
+            ("test", "", 3),
+            ("vendor", "", 4),
+        ],
+    );
+
+    let typed = inventory::iter::<Typed<u32>>.into_iter().next().unwrap();
+    assert_eq!((typed.run)(), 5);
+
+    let setting = inventory::iter::<Setting>.into_iter().next().unwrap();
+    assert_eq!(setting.0, "color");
+    assert!(std::ptr::eq(setting, &COLOR));
+
+    let format = inventory::iter::<dyn Format>.into_iter().next().unwrap();
+    assert_eq!(format.extension(), "json");
+}
+
+pub struct Exported(pub u8);
+
+inventory::collect!(Exported, export = "inventory_test_exported");
+
+inventory::submit!(Exported(1));
+
+#[test]
+fn test_export() {
+    let values: Vec<u8> = inventory::iter::<Exported>
+        .into_iter()
+        .map(|exported| exported.0)
+        .collect();
+    assert_eq!(values, [1]);
+}
+
+#[cfg(feature = "macros")]
+mod derive {
+    #[derive(inventory::Collect)]
+    #[inventory(key = name: str, order = rank)]
+    pub struct Ranked {
+        pub name: &'static str,
+        pub rank: u8,
+    }
+
+    inventory::submit!(Ranked { name: "b", rank: 2 });
+    inventory::submit!(Ranked { name: "a", rank: 3 });
+    inventory::submit! {
+        #![priority(1)]
+        Ranked { name: "c", rank: 2 }
+    }
+    inventory::submit!(Ranked { name: "d", rank: 1 });
+
+    #[derive(inventory::Collect)]
+    #[inventory(unique_by = 0, on_duplicate = KeepLast, export = "inventory_test_unique")]
+    pub struct Unique(pub &'static str, pub u8);
+
+    inventory::submit!(Unique("x", 1));
+    inventory::submit!(Unique("x", 2));
+    inventory::submit!(Unique("y", 3));
+
+    use ::inventory as renamed;
+
+    #[derive(inventory::Collect)]
+    #[inventory(crate = self::renamed, key = 0)]
+    pub struct Renamed(pub &'static str);
+
+    inventory::submit!(Renamed("z"));
+
+    #[cfg(feature = "alloc")]
+    #[derive(inventory::Collect)]
+    pub struct Generic<T: Sync>(pub T);
+
+    #[cfg(feature = "alloc")]
+    inventory::submit!(Generic(1u8));
+    #[cfg(feature = "alloc")]
+    inventory::submit!(Generic("a"));
+}
+
+#[cfg(feature = "macros")]
+#[test]
+fn test_derive() {
+    use derive::{Ranked, Unique};
+
+    let ranked: Vec<&str> = inventory::iter::<Ranked>
+        .into_iter()
+        .map(|ranked| ranked.name)
+        .collect();
+    assert_eq!(ranked, ["d", "c", "b", "a"]);
+    assert_eq!(inventory::get::<Ranked>("b").unwrap().rank, 2);
+
+    let unique: Vec<u8> = inventory::iter::<Unique>
+        .into_iter()
+        .map(|unique| unique.1)
+        .collect();
+    assert_eq!(unique, [2, 3]);
+    assert_eq!(inventory::get::<Unique>("x").unwrap().1, 2);
+
+    assert_eq!(inventory::get::<derive::Renamed>("z").unwrap().0, "z");
+
+    let registries: Vec<&inventory::RegistryInfo> = inventory::registries()
+        .filter(|registry| registry.is::<Ranked>() || registry.is::<Unique>())
+        .collect();
+    assert_eq!(registries.len(), 2);
+    assert_eq!(registries[1].type_name(), "test::derive::Unique");
+    assert_eq!(registries[1].len(), 2);
+
+    #[cfg(feature = "alloc")]
+    {
+        use derive::Generic;
+
+        let bytes: Vec<u8> = inventory::iter::<Generic<u8>>
+            .into_iter()
+            .map(|generic| generic.0)
+            .collect();
+        assert_eq!(bytes, [1]);
+        assert_eq!(inventory::iter::<Generic<&str>>.into_iter().count(), 1);
+        assert_eq!(inventory::iter::<Generic<u16>>.into_iter().count(), 0);
+    }
+}
+
+pub struct Codec(pub u16);
+
+inventory::collect!(Codec, unique_by = 0: u16, on_duplicate = KeepFirst);
+
+inventory::submit! {[
+    Codec(1),
+    Codec(6),
+    Codec(7),
+]}
+
+inventory::submit_all!(&[] as &[Codec]);
+
+static CODECS: [Codec; 3] = [Codec(8), Codec(6), Codec(9)];
+
+inventory::submit_all!(&CODECS);
+
+inventory::submit! {
+    #![priority(1)]
+    [Codec(0)]
+}
+
+#[test]
+fn test_submit_slice() {
+    let codecs: Vec<u16> = inventory::iter_sorted::<Codec>()
+        .map(|codec| codec.0)
+        .collect();
+    assert_eq!(codecs, [0, 1, 6, 7, 8, 9]);
+    assert_eq!(inventory::get::<Codec>(&9).unwrap().0, 9);
+
+    let last = inventory::iter_sorted::<Codec>().last().unwrap();
+    assert!(std::ptr::eq(last, &CODECS[2]));
+}
+
+#[cfg(feature = "alloc")]
+mod subscribe {
+    use std::sync::Mutex;
+
+    pub struct Event(pub &'static str);
+
+    inventory::collect!(Event);
+
+    inventory::submit!(Event("static"));
+
+    pub static FIRST: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+    pub static SECOND: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+    pub fn first(event: &'static Event) {
+        FIRST.lock().unwrap().push(event.0);
+    }
+
+    pub fn second(event: &'static Event) {
+        SECOND.lock().unwrap().push(event.0);
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_subscribe() {
+    use subscribe::{Event, FIRST, SECOND};
+
+    inventory::subscribe::<Event>(subscribe::first);
+    assert_eq!(*FIRST.lock().unwrap(), ["static"]);
+
+    inventory::register_boxed(Box::new(Event("boxed")));
+    assert_eq!(*FIRST.lock().unwrap(), ["static", "boxed"]);
+
+    inventory::subscribe::<Event>(subscribe::second);
+    let mut second = SECOND.lock().unwrap().clone();
+    second.sort_unstable();
+    assert_eq!(second, ["boxed", "static"]);
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| inventory::register_boxed(Box::new(Event("thread"))));
+    });
+    assert_eq!(FIRST.lock().unwrap().len(), 3);
+    assert_eq!(SECOND.lock().unwrap().len(), 3);
+}
+
+pub struct Cached(pub u32);
+
+inventory::collect!(Cached);
+
+inventory::submit!(Cached(1));
+
+#[test]
+fn test_snapshot() {
+    use inventory::{IterSorted, Snapshot};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static BUILDS: AtomicUsize = AtomicUsize::new(0);
+
+    fn sum(cached: IterSorted<Cached>) -> u32 {
+        BUILDS.fetch_add(1, Ordering::Relaxed);
+        cached.map(|cached| cached.0).sum()
+    }
+
+    static LATE: inventory::Entry<Cached> = inventory::Entry::new(&Cached(2));
+
+    let mut snapshot = Snapshot::new(sum);
+    assert_eq!(*snapshot.get(), 1);
+    assert_eq!(*snapshot.get(), 1);
+    assert_eq!(BUILDS.load(Ordering::Relaxed), 1);
+
+    let generation = inventory::generation::<Cached>();
+    inventory::register(&LATE);
+    inventory::register(&LATE);
+    assert_eq!(inventory::generation::<Cached>(), generation + 1);
+    assert_eq!(*snapshot.get(), 3);
+    assert_eq!(BUILDS.load(Ordering::Relaxed), 2);
+
+    snapshot.invalidate();
+    assert_eq!(*snapshot.get(), 3);
+    assert_eq!(BUILDS.load(Ordering::Relaxed), 3);
+}
+
+#[cfg(feature = "ffi")]
+mod ffi {
+    use std::ffi::c_void;
+
+    #[repr(C)]
+    pub struct Handler {
+        pub id: u32,
+    }
+
+    inventory::collect!(Handler, ffi = "inventory_test_handlers");
+
+    inventory::submit!(Handler { id: 1 });
+    inventory::submit! {
+        #![priority(1)]
+        Handler { id: 2 }
+    }
+
+    #[repr(C)]
+    pub struct Failing {
+        pub id: u32,
+    }
+
+    inventory::collect!(Failing, ffi = "inventory_test_failing");
+
+    inventory::submit_lazy!(Failing, panic!("initializer of Failing"));
+
+    extern "C" {
+        pub fn inventory_test_failing_count() -> usize;
+        pub fn inventory_test_failing_get(index: usize) -> *const Failing;
+        pub fn inventory_test_failing_iter_new() -> *mut c_void;
+        pub fn inventory_test_failing_iter_next(iter: *mut c_void) -> *const Failing;
+        pub fn inventory_test_failing_iter_free(iter: *mut c_void);
+        pub fn inventory_test_handlers_count() -> usize;
+        pub fn inventory_test_handlers_get(index: usize) -> *const Handler;
+        pub fn inventory_test_handlers_iter_new() -> *mut c_void;
+        pub fn inventory_test_handlers_iter_next(iter: *mut c_void) -> *const Handler;
+        pub fn inventory_test_handlers_iter_free(iter: *mut c_void);
+    }
+}
+
+#[cfg(feature = "ffi")]
+#[test]
+fn test_ffi() {
+    use ffi::*;
+
+    unsafe {
+        assert_eq!(inventory_test_handlers_count(), 2);
+        assert_eq!((*inventory_test_handlers_get(0)).id, 2);
+        assert_eq!((*inventory_test_handlers_get(1)).id, 1);
+        assert!(inventory_test_handlers_get(2).is_null());
+
+        let iter = inventory_test_handlers_iter_new();
+        assert_eq!((*inventory_test_handlers_iter_next(iter)).id, 2);
+        assert_eq!((*inventory_test_handlers_iter_next(iter)).id, 1);
+        assert!(inventory_test_handlers_iter_next(iter).is_null());
+        inventory_test_handlers_iter_free(iter);
+
+        // A panic is caught rather than unwinding into the caller.
+        assert_eq!(inventory_test_failing_count(), 1);
+        assert!(inventory_test_failing_get(0).is_null());
+        let iter = inventory_test_failing_iter_new();
+        assert!(inventory_test_failing_iter_next(iter).is_null());
+        inventory_test_failing_iter_free(iter);
+    }
+
+    let header = inventory::c_header("handlers", "struct handler");
+    assert!(header.contains("#ifndef INVENTORY_HANDLERS_H\n"));
+    assert!(header.contains("\nsize_t handlers_count(void);\n"));
+    assert!(header.contains("\nconst struct handler *handlers_get(size_t index);\n"));
+    assert!(header.contains("\nconst struct handler *handlers_iter_next(handlers_iter *iter);\n"));
+}
+
+pub struct Toggled(pub &'static str);
+
+inventory::collect!(Toggled, key = 0: str);
+
+inventory::submit!(Toggled("a"));
+inventory::submit!(Toggled("b"));
+inventory::submit!([Toggled("c"), Toggled("d")]);
+
+pub struct ToggledFirst(pub &'static str, pub u32);
+
+inventory::collect!(ToggledFirst, unique_by = 0: str, on_duplicate = KeepFirst);
+
+inventory::submit!(ToggledFirst("a", 1));
+inventory::submit!(ToggledFirst("a", 2));
+
+#[test]
+fn test_disable() {
+    fn names<'a>(iter: impl Iterator<Item = &'a Toggled>) -> Vec<&'a str> {
+        let mut names: Vec<&str> = iter.map(|toggled| toggled.0).collect();
+        names.sort_unstable();
+        names
+    }
+
+    assert!(inventory::get::<Toggled>("b").is_some());
+    let generation = inventory::generation::<Toggled>();
+
+    assert_eq!(inventory::disable::<Toggled>(|toggled| toggled.0 == "b"), 1);
+    assert_ne!(inventory::generation::<Toggled>(), generation);
+    assert_eq!(
+        names(inventory::iter::<Toggled>.into_iter()),
+        ["a", "c", "d"]
+    );
+    assert_eq!(
+        names(inventory::iter_all::<Toggled>()),
+        ["a", "b", "c", "d"]
+    );
+    assert!(inventory::get::<Toggled>("b").is_none());
+
+    // Values submitted together are disabled together.
+    assert_eq!(inventory::disable::<Toggled>(|toggled| toggled.0 == "c"), 1);
+    assert_eq!(names(inventory::iter_sorted::<Toggled>()), ["a"]);
+
+    let generation = inventory::generation::<Toggled>();
+    assert_eq!(inventory::disable::<Toggled>(|toggled| toggled.0 == "b"), 1);
+    assert_eq!(inventory::generation::<Toggled>(), generation);
+
+    assert_eq!(inventory::enable::<Toggled>(|_| true), 4);
+    assert_eq!(
+        names(inventory::iter::<Toggled>.into_iter()),
+        ["a", "b", "c", "d"]
+    );
+    assert!(inventory::get::<Toggled>("b").is_some());
+
+    // A disabled plugin does not hide the plugins that have the same key.
+    let numbers = || -> Vec<u32> {
+        inventory::iter_sorted::<ToggledFirst>()
+            .map(|toggled| toggled.1)
+            .collect()
+    };
+    assert_eq!(numbers(), [1]);
+    assert_eq!(
+        inventory::disable::<ToggledFirst>(|toggled| toggled.1 == 1),
+        1
+    );
+    assert_eq!(numbers(), [2]);
+    assert_eq!(inventory::get::<ToggledFirst>("a").unwrap().1, 2);
+    assert_eq!(inventory::iter_all::<ToggledFirst>().count(), 1);
+}
+
+pub struct Substituted(pub &'static str);
+
+inventory::collect!(Substituted, key = 0: str);
+
+inventory::submit!(Substituted("real"));
+
+#[cfg(feature = "test-util")]
+#[test]
+fn test_with_entries() {
+    use inventory::test::with_entries;
+    use std::panic;
+
+    fn names() -> Vec<&'static str> {
+        inventory::iter::<Substituted>
+            .into_iter()
+            .map(|substituted| substituted.0)
+            .collect()
+    }
+
+    static B: Substituted = Substituted("b");
+    static A: Substituted = Substituted("a");
+
+    let generation = inventory::generation::<Substituted>();
+    with_entries::<Substituted>(&[], || {
+        assert!(names().is_empty());
+        assert!(inventory::get::<Substituted>("real").is_none());
+        assert_ne!(inventory::generation::<Substituted>(), generation);
+    });
+
+    with_entries::<Substituted>(&[&B, &A], || {
+        assert_eq!(names(), ["b", "a"]);
+        assert!(std::ptr::eq(
+            inventory::get::<Substituted>("a").unwrap(),
+            &A
+        ));
+        with_entries::<Substituted>(&[&A], || assert_eq!(names(), ["a"]));
+        assert_eq!(names(), ["b", "a"]);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                assert_eq!(names(), ["real"]);
+                assert!(inventory::get::<Substituted>("real").is_some());
+            });
+        });
+    });
+
+    let result = panic::catch_unwind(|| with_entries::<Substituted>(&[], || panic!()));
+    assert!(result.is_err());
+    assert_eq!(names(), ["real"]);
+    assert!(inventory::get::<Substituted>("real").is_some());
+    assert_eq!(inventory::generation::<Substituted>(), generation);
+
+    with_entries::<dyn Plugin>(&[&EXTERNAL], || {
+        let names: Vec<&str> = inventory::iter::<dyn Plugin>
+            .into_iter()
+            .map(Plugin::name)
+            .collect();
+        assert_eq!(names, ["external"]);
+    });
+}
+
+#[cfg(feature = "alloc")]
+mod ordered {
+    pub struct Hook(pub &'static str);
+
+    inventory::collect!(Hook);
+
+    inventory::submit! {
+        #![id(cache), after(db, config)]
+        Hook("cache")
+    }
+    inventory::submit! {
+        #![id(db)]
+        Hook("db")
+    }
+    inventory::submit!(Hook("unconstrained"));
+    inventory::submit! {
+        #![before(db)]
+        #![id(config)]
+        Hook("config")
+    }
+
+    pub struct Cyclic(pub &'static str);
+
+    inventory::collect!(Cyclic);
+
+    inventory::submit! {
+        #![id(a), after(b)]
+        Cyclic("a")
+    }
+    inventory::submit! {
+        #![id(b), after(c)]
+        Cyclic("b")
+    }
+    inventory::submit! {
+        #![id(c), after(a)]
+        Cyclic("c")
+    }
+    inventory::submit! {
+        #![after(nonexistent)]
+        Cyclic("d")
+    }
+
+    pub struct SelfDependent(pub &'static str);
+
+    inventory::collect!(SelfDependent);
+
+    inventory::submit! {
+        #![id(e), before(e)]
+        SelfDependent("e")
+    }
+    inventory::submit!(SelfDependent("f"));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_iter_ordered() {
+    use ordered::{Cyclic, Hook, SelfDependent};
+
+    let hooks: Vec<&str> = inventory::iter_ordered::<Hook>()
+        .unwrap()
+        .map(|hook| hook.0)
+        .collect();
+    assert_eq!(hooks, ["unconstrained", "config", "db", "cache"]);
+
+    let error = inventory::iter_ordered::<Cyclic>().err().unwrap();
+    let missing: Vec<&str> = error.missing().map(|(_info, id)| id).collect();
+    assert_eq!(missing, ["nonexistent"]);
+    let cycle: Vec<&str> = error.cyclic().map(|(cyclic, _info)| cyclic.0).collect();
+    assert_eq!(cycle, ["a", "c", "b"]);
+
+    let message = error.to_string();
+    assert!(message.starts_with("cannot order plugins of type `test::ordered::Cyclic`:\n"));
+    assert!(message.contains("\n    submission at tests/test.rs:"));
+    assert!(
+        message.contains(" must come after `nonexistent`, which is not the id of any submission")
+    );
+    assert!(message.contains("\n    cycle: `a` at tests/test.rs:"));
+
+    let error = inventory::iter_ordered::<SelfDependent>().err().unwrap();
+    assert_eq!(error.missing().count(), 0);
+    let cycle: Vec<&str> = error.cyclic().map(|(plugin, _info)| plugin.0).collect();
+    assert_eq!(cycle, ["e"]);
+}
+
+#[cfg(all(feature = "manifest", target_os = "linux"))]
+mod manifest {
+    pub struct Described(pub &'static str);
+
+    inventory::collect!(Described);
+
+    inventory::submit! {
+        #![label("the first one")]
+        Described("a")
+    }
+    inventory::submit!([Described("b"), Described("c")]);
+
+    inventory::collect!(Described as pub NAMED);
+
+    inventory::submit!(in NAMED => Described("d"));
+
+    extern "Rust" {
+        #[link_name = "__start_inventory_manifest"]
+        static START: [u8; 0];
+        #[link_name = "__stop_inventory_manifest"]
+        static STOP: [u8; 0];
+    }
+
+    pub struct Record {
+        pub line: u32,
+        pub strings: Vec<String>,
+    }
+
+    pub fn records() -> Vec<Record> {
+        let section = unsafe {
+            let start = std::ptr::addr_of!(START).cast::<u8>();
+            let stop = std::ptr::addr_of!(STOP).cast::<u8>();
+            std::slice::from_raw_parts(start, stop as usize - start as usize)
+        };
+        let mut records = Vec::new();
+        let mut rest = section;
+        while let Some((&version, _)) = rest.split_first() {
+            if version == 0 {
+                rest = &rest[1..];
+                continue;
+            }
+            assert_eq!(version, 1);
+            let len = u32::from_le_bytes(rest[1..5].try_into().unwrap()) as usize;
+            let line = u32::from_le_bytes(rest[5..9].try_into().unwrap());
+            let mut fields = &rest[9..len];
+            let mut strings = Vec::new();
+            while !fields.is_empty() {
+                let n = u16::from_le_bytes([fields[0], fields[1]]) as usize;
+                strings.push(String::from_utf8(fields[2..2 + n].to_vec()).unwrap());
+                fields = &fields[2 + n..];
+            }
+            records.push(Record { line, strings });
+            rest = &rest[len..];
+        }
+        records
+    }
+}
+
+#[cfg(all(feature = "manifest", target_os = "linux"))]
+#[test]
+fn test_manifest() {
+    let records: Vec<manifest::Record> = manifest::records()
+        .into_iter()
+        .filter(|record| record.strings[2] == "test::manifest")
+        .collect();
+    let mut strings: Vec<Vec<&str>> = records
+        .iter()
+        .map(|record| record.strings.iter().map(String::as_str).collect())
+        .collect();
+    strings.sort_unstable();
+    assert_eq!(
+        strings,
+        [
+            ["Described", "test", "test::manifest", "tests/test.rs", ""],
+            [
+                "Described",
+                "test",
+                "test::manifest",
+                "tests/test.rs",
+                "the first one"
+            ],
+            ["NAMED", "test", "test::manifest", "tests/test.rs", ""],
+        ],
+    );
+    assert!(records.iter().all(|record| record.line > 0));
+
+    let described = inventory::iter_sorted::<manifest::Described>();
+    let names: Vec<&str> = described.map(|described| described.0).collect();
+    assert_eq!(names, ["a", "b", "c"]);
+}
+
+mod registries {
+    pub struct Listed;
+
+    inventory::collect!(Listed);
+
+    inventory::submit!(Listed);
+    inventory::submit!([Listed, Listed]);
+
+    inventory::collect!(Listed as pub EXTRA);
+
+    pub trait Checked: Sync {}
+
+    inventory::collect!(dyn Checked);
+}
+
+#[test]
+fn test_registries() {
+    use inventory::{RegistryInfo, SubmissionInfo};
+    use registries::{Checked, Listed};
+
+    let listed: Vec<&RegistryInfo> = inventory::registries()
+        .filter(|registry| registry.is::<Listed>())
+        .collect();
+    assert_eq!(listed.len(), 2);
+
+    let (registry, named) = (listed[0], listed[1]);
+    assert_eq!(registry.type_name(), "test::registries::Listed");
+    assert_eq!(registry.name(), None);
+    assert_eq!(registry.len(), 3);
+    let mut lines: Vec<u32> = registry.submissions().map(SubmissionInfo::line).collect();
+    lines.sort_unstable();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0] < lines[1]);
+
+    assert_eq!(named.name(), Some("EXTRA"));
+    assert!(named.is_empty());
+    assert_eq!(named.submissions().count(), 0);
+
+    let checked = inventory::registries()
+        .find(|registry| registry.is::<dyn Checked>())
+        .unwrap();
+    assert_eq!(checked.type_id(), std::any::TypeId::of::<dyn Checked>());
+    assert!(checked.is_empty());
+}
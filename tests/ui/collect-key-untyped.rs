@@ -0,0 +1,8 @@
+pub struct Flag {
+    short: char,
+    name: &'static str,
+}
+
+inventory::collect!(Flag, key = name);
+
+fn main() {}
@@ -0,0 +1,10 @@
+struct Hook;
+
+inventory::collect!(Hook);
+
+inventory::submit! {
+    #![id(cache), requires(db)]
+    Hook
+}
+
+fn main() {}